@@ -0,0 +1,147 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Semantics/Effects.h"
+#include "uLang/Semantics/Expression.h"
+#include "uLang/Semantics/SemanticFunction.h"
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+static bool RangeContains(const STextRange& Outer, const STextRange& Inner) {
+    const bool bBeginsBefore = Outer.BeginRow() < Inner.BeginRow()
+        || (Outer.BeginRow() == Inner.BeginRow() && Outer.BeginColumn() <= Inner.BeginColumn());
+    const bool bEndsAfter = Outer.EndRow() > Inner.EndRow()
+        || (Outer.EndRow() == Inner.EndRow() && Outer.EndColumn() >= Inner.EndColumn());
+    return bBeginsBefore && bEndsAfter;
+}
+
+static const Vst::Node* FindInnermostNode(const Vst::Node& Node, const STextRange& Range) {
+    for (const auto& Child : Node.GetChildren()) {
+        if (RangeContains(Child->Whence(), Range)) {
+            return FindInnermostNode(*Child, Range);
+        }
+    }
+    return &Node;
+}
+
+static const CExprInvocation* FindEnclosingInvocation(const Vst::Node* Node) {
+    for (; Node; Node = Node->GetParent()) {
+        const CAstNode* AstNode = Node->GetMappedAstNode();
+        if (AstNode && AstNode->GetNodeType() == EAstNodeType::Invoke_Invocation) {
+            return static_cast<const CExprInvocation*>(AstNode);
+        }
+    }
+    return nullptr;
+}
+
+static const CExprFunctionDefinition* FindEnclosingFunctionDefinition(const Vst::Node* Node) {
+    for (; Node; Node = Node->GetParent()) {
+        const CAstNode* AstNode = Node->GetMappedAstNode();
+        if (AstNode && AstNode->GetNodeType() == EAstNodeType::Definition_Function) {
+            return static_cast<const CExprFunctionDefinition*>(AstNode);
+        }
+    }
+    return nullptr;
+}
+
+static const Vst::Node* FindEnclosingStatement(const Vst::Node* Node) {
+    // statements are direct children of block clauses
+    for (; Node && Node->GetParent(); Node = Node->GetParent()) {
+        if (Node->GetParent()->GetElementType() == Vst::NodeType::Clause) {
+            return Node;
+        }
+    }
+    return nullptr;
+}
+
+// Effects of an expression which isn't a plain function call, e.g `Arr[I]` or `Map[Key]`,
+// are only named by the diagnostic message
+static CUTF8String GetMissingEffectsFromMessage(const CUTF8String& Message) {
+    CUTF8StringBuilder Builder;
+    for (const char* Specifier : {"suspends", "decides", "transacts"}) {
+        if (Message.ToStringView().Find(CUTF8String("'%s'", Specifier).ToStringView()) != CUTF8StringView::IndexNone) {
+            if (Builder.IsFilled()) {
+                Builder.Append(' ');
+            }
+            Builder.Append(Specifier);
+        }
+    }
+    return Builder.MoveToString();
+}
+
+static CUTF8String GetMissingEffects(const SEffectSet& CalleeEffects, const SEffectSet& ContextEffects) {
+    CUTF8StringBuilder Builder;
+    auto AddEffect = [&Builder](const char* Specifier) {
+        if (Builder.IsFilled()) {
+            Builder.Append(' ');
+        }
+        Builder.Append(Specifier);
+    };
+
+    if (CalleeEffects[EEffect::suspends] && !ContextEffects[EEffect::suspends]) {
+        AddEffect("suspends");
+    }
+    if (CalleeEffects[EEffect::decides] && !ContextEffects[EEffect::decides]) {
+        AddEffect("decides");
+    }
+    if ((CalleeEffects[EEffect::reads] && !ContextEffects[EEffect::reads])
+        || (CalleeEffects[EEffect::writes] && !ContextEffects[EEffect::writes])
+        || (CalleeEffects[EEffect::allocates] && !ContextEffects[EEffect::allocates])) {
+        AddEffect("transacts");
+    }
+
+    return Builder.MoveToString();
+}
+
+void CollectDiagnosticContext(const LspProjectContainer& ProjectContainer, const SGlitch& Glitch, SDiagnosticContext& OutContext) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(ProjectContainer, Glitch._Locus._SnippetPath.AsCString());
+    if (!SnippetVst) {
+        return;
+    }
+
+    const Vst::Node* Node = FindInnermostNode(*SnippetVst, Glitch._Locus._Range);
+
+    if (const Vst::Identifier* Identifier = Node->AsNullable<Vst::Identifier>()) {
+        OutContext._Identifier = Identifier->GetSourceText();
+        OutContext._Context._Identifier = OutContext._Identifier.AsCString();
+    }
+
+    // a failable definition can't be wrapped as a whole, the binding would go out of scope
+    const Vst::Node* Statement = FindEnclosingStatement(Node);
+    if (Statement && Statement->GetElementType() != Vst::NodeType::Definition) {
+        OutContext._Context._bHasStatementSpan = true;
+        OutContext._Context._StatementSpan = TextRangeToSpan(Statement->Whence());
+    }
+
+    const CExprFunctionDefinition* FunctionDefinition = FindEnclosingFunctionDefinition(Node);
+    if (!FunctionDefinition) {
+        return;
+    }
+
+    // specifiers go right after the parameters, e.g `F(X:int)<suspends>:void =`
+    const TSPtr<CExpressionBase>& Element = FunctionDefinition->Element();
+    if (const Vst::Node* ElementVstNode = Element ? Element->GetMappedVstNode() : nullptr) {
+        OutContext._Context._bHasSpecifiersPosition = true;
+        OutContext._Context._SpecifiersRow = ElementVstNode->Whence().EndRow();
+        OutContext._Context._SpecifiersColumn = ElementVstNode->Whence().EndColumn();
+    }
+
+    const CExprInvocation* Invocation = FindEnclosingInvocation(Node);
+    const CExpressionBase* Callee = Invocation ? Invocation->GetCallee().Get() : nullptr;
+    if (Callee && Callee->GetNodeType() == EAstNodeType::Identifier_Function) {
+        const CFunction& CalleeFunction = static_cast<const CExprIdentifierFunction*>(Callee)->_Function;
+        OutContext._MissingEffects = GetMissingEffects(
+                CalleeFunction._Signature.GetEffects(), FunctionDefinition->_Function->_Signature.GetEffects());
+    } else if (Glitch._Result.GetInfo().ReferenceCode == EffectNotAllowedReferenceCode) {
+        OutContext._MissingEffects = GetMissingEffectsFromMessage(Glitch._Result._Message);
+    }
+    if (OutContext._MissingEffects.IsFilled()) {
+        OutContext._Context._MissingEffects = OutContext._MissingEffects.AsCString();
+    }
+}
+
+} // namespace Verse::LspCE
@@ -0,0 +1,89 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+class CVstFoldingRangesVisitor final {
+public:
+    CVstFoldingRangesVisitor(RsFoldingRangesAccumulator* FoldingRangesAccumulator)
+        : _FoldingRangesAccumulator(FoldingRangesAccumulator)
+        {}
+
+    void Visit(const Vst::Node& Node) {
+        switch (Node.GetElementType()) {
+        case Vst::NodeType::Comment:
+            // single line comments are grouped together on the Rust side
+            EmitFoldingRange(Node, RsFoldingRangeKind::Comment);
+            break;
+        case Vst::NodeType::Definition:
+            EmitMultilineFoldingRange(Node, RsFoldingRangeKind::Region);
+            break;
+        case Vst::NodeType::Clause:
+            // bodies of macros such as `if`, `for`, `case`, `block` or `class`, either indented or braced
+            EmitMultilineFoldingRange(Node, RsFoldingRangeKind::Region);
+            break;
+        case Vst::NodeType::Macro: {
+            const Vst::Macro& MacroNode = Node.As<Vst::Macro>();
+            if (const Vst::Identifier* MacroIdentifier = MacroNode.GetName()->AsNullable<Vst::Identifier>()) {
+                if (MacroIdentifier->GetSourceText() == "using") {
+                    // consecutive using lines are grouped together on the Rust side
+                    EmitFoldingRange(Node, RsFoldingRangeKind::Imports);
+                    return;
+                }
+            }
+            break;
+        }
+        default:
+            break;
+        }
+
+        for (const auto& Child : Node.GetPrefixComments()) {
+            Visit(*Child);
+        }
+        for (const auto& Child : Node.GetChildren()) {
+            Visit(*Child);
+        }
+        for (const auto& Child : Node.GetPostfixComments()) {
+            Visit(*Child);
+        }
+    }
+
+private:
+    RsFoldingRangesAccumulator* _FoldingRangesAccumulator;
+
+    void EmitFoldingRange(const Vst::Node& OriginNode, RsFoldingRangeKind Kind) {
+        RsFoldingRangeEntry FoldingRangeEntry = {
+            ._Kind = Kind,
+            ._Span = TextRangeToSpan(OriginNode.Whence()),
+        };
+        RS_AddFoldingRange(_FoldingRangesAccumulator, FoldingRangeEntry);
+    }
+
+    void EmitMultilineFoldingRange(const Vst::Node& OriginNode, RsFoldingRangeKind Kind) {
+        const STextRange& Range = OriginNode.Whence();
+        if (Range.EndRow() > Range.BeginRow()) {
+            EmitFoldingRange(OriginNode, Kind);
+        }
+    }
+};
+
+} // namespace Verse::LspCE
+
+extern "C" void Lsp_FoldingRanges(
+    LspProjectContainer* ProjectContainer,
+    const char* Path,
+    RsFoldingRangesAccumulator* FoldingRangesAccumulator
+) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
+    if (!SnippetVst) {
+        return;
+    }
+
+    CVstFoldingRangesVisitor Visitor(FoldingRangesAccumulator);
+    Visitor.Visit(*SnippetVst);
+}
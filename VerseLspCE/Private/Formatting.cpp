@@ -0,0 +1,452 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Common/Text/UTF8StringBuilder.h"
+#include "uLang/SourceProject/SourceProject.h"
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+static bool IsBlank(UTF8Char Char) {
+    return Char == ' ' || Char == '\t' || Char == '\r';
+}
+
+static CUTF8StringView TrimStart(CUTF8StringView View) {
+    while (View._Begin < View._End && IsBlank(*View._Begin)) {
+        ++View._Begin;
+    }
+    return View;
+}
+
+static CUTF8StringView TrimEnd(CUTF8StringView View) {
+    while (View._End > View._Begin && IsBlank(*(View._End - 1))) {
+        --View._End;
+    }
+    return View;
+}
+
+static bool IsClosingBracket(UTF8Char Char) {
+    return Char == '}' || Char == ')' || Char == ']';
+}
+
+// Whether two texts only differ by whitespace and statement separators
+static bool IsEquivalent(const CUTF8StringView& Lhs, const CUTF8StringView& Rhs) {
+    auto IsSignificant = [](UTF8Char Char) {
+        return !IsBlank(Char) && Char != '\n' && Char != ';';
+    };
+
+    const UTF8Char* LhsChar = Lhs._Begin;
+    const UTF8Char* RhsChar = Rhs._Begin;
+    for (;;) {
+        while (LhsChar < Lhs._End && !IsSignificant(*LhsChar)) {
+            ++LhsChar;
+        }
+        while (RhsChar < Rhs._End && !IsSignificant(*RhsChar)) {
+            ++RhsChar;
+        }
+        if (LhsChar == Lhs._End || RhsChar == Rhs._End) {
+            return LhsChar == Lhs._End && RhsChar == Rhs._End;
+        }
+        if (*LhsChar++ != *RhsChar++) {
+            return false;
+        }
+    }
+}
+
+static bool ContainsParseError(const Vst::Node& Node) {
+    if (Node.GetElementType() == Vst::NodeType::ParseError) {
+        return true;
+    }
+    for (const auto& Child : Node.GetChildren()) {
+        if (ContainsParseError(*Child)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+static const ISourceSnippet* FindSourceSnippet(const CSourceModule& Module, const CUTF8StringView& Path) {
+    for (const auto& Snippet : Module._SourceSnippets) {
+        if (Snippet->GetPath() == Path) {
+            return &*Snippet;
+        }
+    }
+    for (const auto& Submodule : Module._Submodules) {
+        if (const ISourceSnippet* Snippet = FindSourceSnippet(*Submodule, Path)) {
+            return Snippet;
+        }
+    }
+    return nullptr;
+}
+
+// Pretty-prints VST nodes from the source text they were parsed from.
+// Only whitespace between nodes is rewritten: each statement of a block goes on its own line at its indentation level,
+// and the text between nodes, including prefix and postfix comments, is otherwise kept as is.
+class CVstFormatter final {
+public:
+    CVstFormatter(const CUTF8StringView& Text, const RsFormattingOptions& Options)
+        : _Text(Text)
+        , _Options(Options)
+    {
+        _LineOffsets.Add(0);
+        for (const UTF8Char* Char = Text._Begin; Char < Text._End; ++Char) {
+            if (*Char == '\n') {
+                _LineOffsets.Add(int32_t(Char + 1 - Text._Begin));
+            }
+        }
+    }
+
+    // Returns false if formatting would change more than whitespace,
+    // which happens when the VST is out of date with the source text
+    bool Format(const Vst::Snippet& SnippetVst) {
+        if (_Options._bHasRange) {
+            FormatStatementsInRange(SnippetVst, 0);
+            return !_bFailed;
+        }
+
+        const uint32_t EndRow = uint32_t(_LineOffsets.Num() - 1);
+        const uint32_t EndColumn = uint32_t(_Text.ByteLen() - _LineOffsets[EndRow]);
+
+        ResetBuilder();
+        // top-level statements are at indentation level 0
+        AppendChildren(SnippetVst, 0, 0, EndRow, EndColumn, -1, true);
+        _Builder.Append('\n');
+        AddEdit(0, 0, EndRow, EndColumn, _Builder.MoveToString());
+        return !_bFailed;
+    }
+
+    void EmitTextEdits(RsTextEditsAccumulator* TextEditsAccumulator) const {
+        for (const SEdit& Edit : _Edits) {
+            RsTextEdit TextEdit = {
+                ._Span = Edit._Span,
+                ._NewText = Edit._NewText.AsCString(),
+            };
+            // AddTextEdit creates owned Rust strings from char* pointers
+            RS_AddTextEdit(TextEditsAccumulator, TextEdit);
+        }
+    }
+
+private:
+    enum class EGap {
+        // Between nodes of an expression
+        Inline,
+        // Between the start of a block and its first statement
+        Opening,
+        // Between two statements of a block
+        Separator,
+        // Between the last statement of a block and its end
+        Closing,
+    };
+
+    struct SEdit {
+        RsSourceSpan _Span;
+        CUTF8String _NewText;
+    };
+
+    CUTF8StringView _Text;
+    RsFormattingOptions _Options;
+    TArray<int32_t> _LineOffsets;
+
+    TArray<SEdit> _Edits;
+    bool _bFailed = false;
+
+    CUTF8StringBuilder _Builder;
+    // Line breaks are only written once followed by text, so that trailing whitespace never is
+    int32_t _PendingLineBreaks = 0;
+    int32_t _PendingIndent = 0;
+
+    void FormatStatementsInRange(const Vst::Node& BlockNode, int32_t Indent) {
+        for (const auto& Child : BlockNode.GetChildren()) {
+            const STextRange& Range = Child->Whence();
+            if (Range.EndRow() < _Options._BeginRow || Range.BeginRow() > _Options._EndRow) {
+                continue;
+            }
+
+            // statements partially in range only get their nested blocks formatted
+            if (Range.BeginRow() >= _Options._BeginRow && Range.EndRow() <= _Options._EndRow && IsLineStart(Range)) {
+                FormatStatement(*Child, Indent);
+            } else {
+                FormatNestedStatementsInRange(*Child, Indent);
+            }
+        }
+    }
+
+    void FormatNestedStatementsInRange(const Vst::Node& Node, int32_t Indent) {
+        for (const auto& Child : Node.GetChildren()) {
+            if (IsBlock(*Child, Indent)) {
+                FormatStatementsInRange(*Child, Indent + 1);
+            } else {
+                FormatNestedStatementsInRange(*Child, Indent);
+            }
+        }
+    }
+
+    void FormatStatement(const Vst::Node& Node, int32_t Indent) {
+        const STextRange& Range = Node.Whence();
+
+        ResetBuilder();
+        AppendIndent(Indent);
+        AppendNode(Node, Indent);
+
+        // the leading indentation is replaced as well
+        AddEdit(Range.BeginRow(), 0, Range.EndRow(), Range.EndColumn(), _Builder.MoveToString());
+    }
+
+    void AddEdit(uint32_t BeginRow, uint32_t BeginColumn, uint32_t EndRow, uint32_t EndColumn, CUTF8String&& NewText) {
+        const CUTF8StringView Original = Slice(BeginRow, BeginColumn, EndRow, EndColumn);
+        if (!IsEquivalent(Original, NewText)) {
+            _bFailed = true;
+            return;
+        }
+        if (Original == NewText) {
+            return;
+        }
+
+        SEdit Edit = {
+            ._Span = {
+                ._BeginRow = BeginRow,
+                ._BeginColumn = BeginColumn,
+                ._EndRow = EndRow,
+                ._EndColumn = EndColumn,
+            },
+            ._NewText = Move(NewText),
+        };
+        _Edits.Add(Move(Edit));
+    }
+
+    void AppendNode(const Vst::Node& Node, int32_t Indent) {
+        const STextRange& Range = Node.Whence();
+        if (IsVerbatim(Node) || Node.GetChildren().IsEmpty()) {
+            AppendText(Slice(Range.BeginRow(), Range.BeginColumn(), Range.EndRow(), Range.EndColumn()));
+            return;
+        }
+
+        AppendChildren(Node, Range.BeginRow(), Range.BeginColumn(), Range.EndRow(), Range.EndColumn(),
+                Indent, IsBlock(Node, Indent));
+    }
+
+    void AppendChildren(
+        const Vst::Node& Node,
+        uint32_t BeginRow, uint32_t BeginColumn,
+        uint32_t EndRow, uint32_t EndColumn,
+        int32_t Indent,
+        bool bBlock
+    ) {
+        const int32_t ChildIndent = bBlock ? Indent + 1 : Indent;
+
+        uint32_t Row = BeginRow;
+        uint32_t Column = BeginColumn;
+        bool bFirst = true;
+        for (const auto& Child : Node.GetChildren()) {
+            const STextRange& ChildRange = Child->Whence();
+            const EGap Kind = !bBlock ? EGap::Inline : (bFirst ? EGap::Opening : EGap::Separator);
+            AppendGap(Slice(Row, Column, ChildRange.BeginRow(), ChildRange.BeginColumn()), Kind, Indent);
+            AppendNode(*Child, ChildIndent);
+
+            Row = ChildRange.EndRow();
+            Column = ChildRange.EndColumn();
+            bFirst = false;
+        }
+        AppendGap(Slice(Row, Column, EndRow, EndColumn), bBlock ? EGap::Closing : EGap::Inline, Indent);
+    }
+
+    void AppendGap(const CUTF8StringView& Gap, EGap Kind, int32_t Indent) {
+        const bool bBlock = Kind != EGap::Inline;
+        // blank lines are kept between statements, at most one at a time
+        const bool bAllowBlankLines = Kind == EGap::Separator || Indent < 0;
+
+        int32_t LineBreaks = 0;
+        const UTF8Char* LineBegin = Gap._Begin;
+        for (const UTF8Char* Char = Gap._Begin; ; ++Char) {
+            if (Char < Gap._End && *Char != '\n') {
+                continue;
+            }
+
+            const bool bLastLine = Char == Gap._End;
+            const CUTF8StringView Line(LineBegin, Char);
+            CUTF8StringView Content = bBlock || !bLastLine ? TrimEnd(Line) : Line;
+            if (bBlock || LineBreaks > 0) {
+                Content = TrimStart(Content);
+            }
+            if (bBlock && Content.IsFilled() && *Content._Begin == ';') {
+                // statement separators are replaced by line breaks
+                ++Content._Begin;
+                Content = TrimStart(Content);
+            }
+
+            if (TrimStart(Content).IsEmpty()) {
+                if (!bBlock && LineBreaks == 0) {
+                    // spacing between nodes on the same line
+                    AppendText(Content);
+                }
+            } else {
+                const bool bClosing = IsClosingBracket(*Content._Begin);
+                if (LineBreaks > 0 || (Kind == EGap::Closing && bClosing)) {
+                    const int32_t LineIndent = bClosing ? Indent : Indent + 1;
+                    BreakLine(LineIndent, bAllowBlankLines && LineBreaks > 1);
+                } else if (bBlock && Line._Begin < Content._Begin && _Builder.IsFilled()) {
+                    AppendText(" ");
+                }
+                AppendText(Content);
+                LineBreaks = 0;
+            }
+
+            if (bLastLine) {
+                break;
+            }
+            ++LineBreaks;
+            LineBegin = Char + 1;
+        }
+
+        if (Kind == EGap::Opening || Kind == EGap::Separator) {
+            BreakLine(Indent + 1, bAllowBlankLines && LineBreaks > 1);
+        } else if (LineBreaks > 0) {
+            // continuation lines are indented once more than the statement they belong to
+            BreakLine(Kind == EGap::Closing ? Indent : Indent + 1, false);
+        }
+    }
+
+    bool IsBlock(const Vst::Node& Node, int32_t Indent) const {
+        if (Node.GetElementType() != Vst::NodeType::Clause) {
+            return false;
+        }
+
+        const STextRange& Range = Node.Whence();
+        if (Range.EndRow() > Range.BeginRow()) {
+            return true;
+        }
+
+        // braced clauses with several statements are split into one statement per line once too long
+        const auto& Children = Node.GetChildren();
+        if (Children.Num() < 2) {
+            return false;
+        }
+        const STextRange& FirstChildRange = Children[0]->Whence();
+        const CUTF8StringView Opening = Slice(Range.BeginRow(), Range.BeginColumn(), FirstChildRange.BeginRow(), FirstChildRange.BeginColumn());
+        bool bBraced = false;
+        for (const UTF8Char* Char = Opening._Begin; Char < Opening._End; ++Char) {
+            bBraced |= *Char == '{';
+        }
+        if (!bBraced) {
+            return false;
+        }
+
+        const CUTF8StringView LineText = TrimStart(TrimEnd(Slice(Range.BeginRow(), 0, Range.BeginRow() + 1, 0)));
+        const int32_t LineLength = Indent * int32_t(_Options._IndentWidth) + LineText.ByteLen();
+        return LineLength > int32_t(_Options._LineLength);
+    }
+
+    static bool IsVerbatim(const Vst::Node& Node) {
+        switch (Node.GetElementType()) {
+        case Vst::NodeType::Comment:
+        case Vst::NodeType::StringLiteral:
+        case Vst::NodeType::CharLiteral:
+        case Vst::NodeType::InterpolatedString:
+            return true;
+        default:
+            return false;
+        }
+    }
+
+    bool IsLineStart(const STextRange& Range) const {
+        return TrimStart(Slice(Range.BeginRow(), 0, Range.BeginRow(), Range.BeginColumn())).IsEmpty();
+    }
+
+    CUTF8StringView Slice(uint32_t BeginRow, uint32_t BeginColumn, uint32_t EndRow, uint32_t EndColumn) const {
+        const int32_t Begin = Offset(BeginRow, BeginColumn);
+        const int32_t End = Offset(EndRow, EndColumn);
+        if (End <= Begin) {
+            return CUTF8StringView(_Text._Begin + Begin, _Text._Begin + Begin);
+        }
+        return CUTF8StringView(_Text._Begin + Begin, _Text._Begin + End);
+    }
+
+    int32_t Offset(uint32_t Row, uint32_t Column) const {
+        if (Row >= uint32_t(_LineOffsets.Num())) {
+            return _Text.ByteLen();
+        }
+        const int32_t Offset = _LineOffsets[Row] + int32_t(Column);
+        return Offset < _Text.ByteLen() ? Offset : _Text.ByteLen();
+    }
+
+    void ResetBuilder() {
+        _Builder = CUTF8StringBuilder();
+        _PendingLineBreaks = 0;
+        _PendingIndent = 0;
+    }
+
+    void BreakLine(int32_t Indent, bool bBlankLine) {
+        const int32_t LineBreaks = bBlankLine ? 2 : 1;
+        _PendingLineBreaks = _PendingLineBreaks > LineBreaks ? _PendingLineBreaks : LineBreaks;
+        _PendingIndent = Indent > 0 ? Indent : 0;
+    }
+
+    void AppendIndent(int32_t Indent) {
+        if (_Options._bIndentWithTabs) {
+            for (int32_t Index = 0; Index < Indent; ++Index) {
+                _Builder.Append('\t');
+            }
+            return;
+        }
+        for (int32_t Index = 0; Index < Indent * int32_t(_Options._IndentWidth); ++Index) {
+            _Builder.Append(' ');
+        }
+    }
+
+    void AppendText(CUTF8StringView Text) {
+        if (_PendingLineBreaks > 0) {
+            Text = TrimStart(Text);
+            if (Text.IsEmpty()) {
+                return;
+            }
+            // nothing to break from at the start of an edit
+            if (_Builder.IsFilled()) {
+                for (int32_t Index = 0; Index < _PendingLineBreaks; ++Index) {
+                    _Builder.Append('\n');
+                }
+                AppendIndent(_PendingIndent);
+            }
+            _PendingLineBreaks = 0;
+        }
+        _Builder.Append(Text);
+    }
+};
+
+} // namespace Verse::LspCE
+
+extern "C" RsFormattingResult Lsp_Format(
+    LspProjectContainer* ProjectContainer,
+    const char* Path,
+    RsFormattingOptions Options,
+    RsTextEditsAccumulator* TextEditsAccumulator
+) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
+    if (!SnippetVst) {
+        return RsFormattingResult::NoSyntaxTree;
+    }
+    if (ContainsParseError(*SnippetVst)) {
+        return RsFormattingResult::SyntaxErrors;
+    }
+
+    const ISourceSnippet* Snippet = nullptr;
+    for (const CSourceProject::SPackage& Package : ProjectContainer->_Project->_Packages) {
+        Snippet = FindSourceSnippet(*Package._Package->_RootModule, Path);
+        if (Snippet) {
+            break;
+        }
+    }
+    const TOptional<CUTF8String> Text = Snippet ? Snippet->GetText() : TOptional<CUTF8String>();
+    if (!Text) {
+        return RsFormattingResult::NoSyntaxTree;
+    }
+
+    CVstFormatter Formatter(*Text, Options);
+    if (!Formatter.Format(*SnippetVst)) {
+        return RsFormattingResult::OutOfDate;
+    }
+    Formatter.EmitTextEdits(TextEditsAccumulator);
+    return RsFormattingResult::Formatted;
+}
@@ -0,0 +1,253 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Semantics/Effects.h"
+#include "uLang/Semantics/Expression.h"
+#include "uLang/Semantics/SemanticFunction.h"
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+#define INLAY_HINT_EFFECTS(v) \
+    v(EEffect::suspends, "suspends") \
+    v(EEffect::decides, "decides") \
+
+#define INLAY_HINT_EFFECT_SETS(v) \
+    v(EffectSets::Transacts, "transacts") \
+    v(EffectSets::Computes, "computes") \
+    v(EffectSets::Converges, "converges") \
+    v(EffectSets::Reads, "reads") \
+    v(EffectSets::Writes, "writes") \
+    v(EffectSets::Allocates, "allocates") \
+
+class CInlayHintsVisitor final : public SAstVisitor {
+public:
+    CInlayHintsVisitor(RsInlayHintsAccumulator* InlayHintsAccumulator)
+        : _InlayHintsAccumulator(InlayHintsAccumulator)
+        {}
+
+    virtual void Visit(const char* /*FieldName*/, CAstNode& AstNode) override {
+        VisitElement(AstNode);
+    }
+
+    virtual void VisitElement(CAstNode& AstNode) override {
+        switch (AstNode.GetNodeType()) {
+        case EAstNodeType::Definition_Data:
+            VisitDataDefinition(static_cast<CExprDataDefinition&>(AstNode));
+            break;
+        case EAstNodeType::Definition_Function:
+            VisitFunctionDefinition(static_cast<CExprFunctionDefinition&>(AstNode));
+            break;
+        case EAstNodeType::Invoke_Invocation:
+            VisitInvocation(static_cast<CExprInvocation&>(AstNode));
+            break;
+        case EAstNodeType::Iteration: {
+            // only the generator and filter clauses bind iteration variables, not the body
+            const CExprIteration& Iteration = static_cast<CExprIteration&>(AstNode);
+            const bool bWasInIteration = _bInIteration;
+            _bInIteration = true;
+            for (const TSRef<CExpressionBase>& Filter : Iteration._Filters) {
+                VisitElement(*Filter);
+            }
+            _bInIteration = bWasInIteration;
+            if (Iteration._Body) {
+                VisitElement(*Iteration._Body);
+            }
+            return;
+        }
+        default:
+            break;
+        }
+
+        VisitAll(AstNode);
+    }
+
+    void VisitAll(const CAstNode& AstNode) {
+        AstNode.VisitImmediates(*this);
+        AstNode.VisitChildren(*this);
+    }
+
+private:
+    RsInlayHintsAccumulator* _InlayHintsAccumulator;
+
+    bool _bInIteration = false;
+
+    void VisitDataDefinition(const CExprDataDefinition& DataDefinition) {
+        // iteration variables never have an explicit type, `X := ...` bindings have no value domain
+        if (!_bInIteration && DataDefinition.ValueDomain()) {
+            return;
+        }
+
+        const Vst::Node* VstNode = DataDefinition.GetMappedVstNode();
+        const CTypeBase* Type = DataDefinition._DataMember->GetType();
+        if (!VstNode || !Type) {
+            return;
+        }
+
+        const Vst::Node& NameNode = FindIdentifierNode(*VstNode, DataDefinition._DataMember->AsNameStringView());
+        const CUTF8String Label(":%s", Type->AsCode().AsCString());
+        EmitInlayHint(_bInIteration ? RsInlayHintKind::IterationType : RsInlayHintKind::BindingType,
+                Label, NameNode.Whence().EndRow(), NameNode.Whence().EndColumn());
+    }
+
+    void VisitFunctionDefinition(const CExprFunctionDefinition& FunctionDefinition) {
+        const CFunction& Function = *FunctionDefinition._Function;
+        const SEffectSet Effects = Function._Signature.GetEffects();
+
+        const TSPtr<CExpressionBase>& Element = FunctionDefinition.Element();
+        const Vst::Node* ElementVstNode = Element ? Element->GetMappedVstNode() : nullptr;
+        // effects written out by the user need no hint
+        if (!ElementVstNode || HasEffectSpecifiers(*ElementVstNode)) {
+            return;
+        }
+
+        const CUTF8String Label = GetEffectSpecifiers(Effects);
+        if (Label.IsFilled()) {
+            EmitInlayHint(RsInlayHintKind::Effects,
+                    Label, ElementVstNode->Whence().EndRow(), ElementVstNode->Whence().EndColumn());
+        }
+    }
+
+    void VisitInvocation(const CExprInvocation& Invocation) {
+        const CExpressionBase* Callee = Invocation.GetCallee().Get();
+        if (!Callee || Callee->GetNodeType() != EAstNodeType::Identifier_Function) {
+            return;
+        }
+        const CFunction& Function = static_cast<const CExprIdentifierFunction*>(Callee)->_Function;
+        const auto& Params = Function._Signature.GetParams();
+
+        const CExpressionBase* Argument = Invocation.GetArgument().Get();
+        if (!Argument) {
+            return;
+        }
+
+        TArray<const CExpressionBase*> Arguments;
+        if (Argument->GetNodeType() == EAstNodeType::Invoke_MakeTuple && Params.Num() > 1) {
+            for (const auto& SubExpr : static_cast<const CExprMakeTuple*>(Argument)->GetSubExprs()) {
+                Arguments.Add(SubExpr.Get());
+            }
+        } else {
+            Arguments.Add(Argument);
+        }
+
+        for (int32_t Index = 0; Index < Arguments.Num() && Index < Params.Num(); Index++) {
+            const CExpressionBase* ArgumentExpr = Arguments[Index];
+            const Vst::Node* ArgumentVstNode = ArgumentExpr ? ArgumentExpr->GetMappedVstNode() : nullptr;
+            if (!ArgumentVstNode) {
+                continue;
+            }
+
+            // named arguments and arguments named like the parameter speak for themselves
+            const CUTF8StringView ParamName = Params[Index]->AsNameStringView();
+            if (const Vst::Identifier* Identifier = ArgumentVstNode->AsNullable<Vst::Identifier>()) {
+                if (Identifier->GetSourceText() == ParamName) {
+                    continue;
+                }
+            }
+            if (ArgumentVstNode->GetElementType() == Vst::NodeType::Definition) {
+                continue;
+            }
+
+            const CUTF8String Label("%s:", Params[Index]->AsNameCString());
+            EmitInlayHint(RsInlayHintKind::ParameterName,
+                    Label, ArgumentVstNode->Whence().BeginRow(), ArgumentVstNode->Whence().BeginColumn());
+        }
+    }
+
+    static bool IsEffectSpecifier(const Vst::Node& Node) {
+        const Vst::Identifier* Identifier = Node.AsNullable<Vst::Identifier>();
+        if (!Identifier) {
+            return false;
+        }
+        const CUTF8StringView Name = Identifier->GetSourceText();
+    #define VISIT_EFFECT(Effect, Specifier) \
+        if (Name == Specifier) { \
+            return true; \
+        }
+        INLAY_HINT_EFFECTS(VISIT_EFFECT)
+        INLAY_HINT_EFFECT_SETS(VISIT_EFFECT)
+    #undef VISIT_EFFECT
+        return Name == "no_rollback" || Name == "varies" || Name == "predicts";
+    }
+
+    static bool HasEffectSpecifiers(const Vst::Node& ElementVstNode) {
+        // specifiers are attached to the element, e.g `F()<decides>`, or to its parameters clause
+        auto AuxHasEffectSpecifier = [](const Vst::Node& Node) {
+            if (const TSPtr<Vst::Clause>& Aux = Node.GetAux()) {
+                for (const auto& Specifier : Aux->GetChildren()) {
+                    if (IsEffectSpecifier(*Specifier)) {
+                        return true;
+                    }
+                }
+            }
+            return false;
+        };
+        if (AuxHasEffectSpecifier(ElementVstNode)) {
+            return true;
+        }
+        for (const auto& Child : ElementVstNode.GetChildren()) {
+            if (AuxHasEffectSpecifier(*Child)) {
+                return true;
+            }
+        }
+        return false;
+    }
+
+    CUTF8String GetEffectSpecifiers(const SEffectSet& Effects) {
+        CUTF8StringBuilder Builder;
+        SEffectSet BaseEffects = Effects;
+
+    #define VISIT_EFFECT(Effect, Specifier) \
+        if (Effects[Effect]) { \
+            Builder.Append("<" Specifier ">"); \
+            BaseEffects = BaseEffects.With(Effect, false); \
+        }
+        INLAY_HINT_EFFECTS(VISIT_EFFECT)
+    #undef VISIT_EFFECT
+
+    #define VISIT_EFFECT_SET(EffectSet, Specifier) \
+        if (BaseEffects == EffectSet) { \
+            Builder.Append("<" Specifier ">"); \
+            return Builder.MoveToString(); \
+        }
+        INLAY_HINT_EFFECT_SETS(VISIT_EFFECT_SET)
+    #undef VISIT_EFFECT_SET
+
+        return Builder.MoveToString();
+    }
+
+    void EmitInlayHint(RsInlayHintKind Kind, const CUTF8String& Label, uint32_t Row, uint32_t Column) {
+        RsInlayHint InlayHint = {
+            ._Kind = Kind,
+            ._Label = Label.AsCString(),
+            ._Row = Row,
+            ._Column = Column,
+        };
+        // AddInlayHint creates owned Rust strings from char* pointers
+        RS_AddInlayHint(_InlayHintsAccumulator, InlayHint);
+    }
+};
+
+} // namespace Verse::LspCE
+
+extern "C" void Lsp_InlayHints(
+    LspProjectContainer* ProjectContainer,
+    const char* Path,
+    RsInlayHintsAccumulator* InlayHintsAccumulator
+) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
+    if (!SnippetVst) {
+        return;
+    }
+
+    const CAstNode* AstNode = SnippetVst->GetMappedAstNode();
+    if (!AstNode) {
+        return;
+    }
+
+    CInlayHintsVisitor Visitor(InlayHintsAccumulator);
+    Visitor.VisitAll(*AstNode);
+}
@@ -0,0 +1,63 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+class CVstSelectionRangeVisitor final {
+public:
+    CVstSelectionRangeVisitor(RsSelectionRangeAccumulator* SelectionRangeAccumulator, uint32_t Row, uint32_t Column)
+        : _SelectionRangeAccumulator(SelectionRangeAccumulator)
+        , _Row(Row)
+        , _Column(Column)
+        {}
+
+    // Emits ranges of all nodes containing the position, from outermost to innermost.
+    void Visit(const Vst::Node& Node) {
+        if (!Contains(Node.Whence())) {
+            return;
+        }
+        RS_AddSelectionRange(_SelectionRangeAccumulator, TextRangeToSpan(Node.Whence()));
+
+        for (const auto& Child : Node.GetChildren()) {
+            if (Contains(Child->Whence())) {
+                Visit(*Child);
+                return;
+            }
+        }
+    }
+
+private:
+    RsSelectionRangeAccumulator* _SelectionRangeAccumulator;
+
+    uint32_t _Row;
+    uint32_t _Column;
+
+    bool Contains(const STextRange& Range) const {
+        const bool bAfterBegin = _Row > Range.BeginRow() || (_Row == Range.BeginRow() && _Column >= Range.BeginColumn());
+        const bool bBeforeEnd = _Row < Range.EndRow() || (_Row == Range.EndRow() && _Column <= Range.EndColumn());
+        return bAfterBegin && bBeforeEnd;
+    }
+};
+
+} // namespace Verse::LspCE
+
+extern "C" void Lsp_SelectionRange(
+    LspProjectContainer* ProjectContainer,
+    const char* Path,
+    uint32_t Row,
+    uint32_t Column,
+    RsSelectionRangeAccumulator* SelectionRangeAccumulator
+) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
+    if (!SnippetVst) {
+        return;
+    }
+
+    CVstSelectionRangeVisitor Visitor(SelectionRangeAccumulator, Row, Column);
+    Visitor.Visit(*SnippetVst);
+}
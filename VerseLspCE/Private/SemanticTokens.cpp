@@ -2,6 +2,12 @@
 
 #include "uLang/Common/Text/FilePathUtils.h"
 #include "uLang/Common/Text/Symbol.h"
+#include "uLang/Semantics/Expression.h"
+#include "uLang/Semantics/SemanticClass.h"
+#include "uLang/Semantics/SemanticEnumeration.h"
+#include "uLang/Semantics/SemanticFunction.h"
+#include "uLang/Semantics/SemanticInterface.h"
+#include "uLang/Semantics/TypeAlias.h"
 #include "uLang/Syntax/VstNode.h"
 #include "uLang/Parser/ReservedSymbols.h"
 
@@ -17,8 +23,9 @@ namespace Verse::LspCE
 
 class CSemanticTokensVisitor final : public SAstVisitor {
 public:
-    CSemanticTokensVisitor(RsSemanticTokensAccumulator* TokenAccumulator, CSymbolTable& Symbols)
+    CSemanticTokensVisitor(RsSemanticTokensAccumulator* TokenAccumulator, CSymbolTable& Symbols, const CSemanticProgram& Program)
         : _TokenAccumulator(TokenAccumulator)
+        , _Program(Program)
         , _ReservedSymbols(Symbols)
         {}
 
@@ -27,32 +34,29 @@ public:
     }
 
     virtual void VisitElement(CAstNode& AstNode) override {
-        // fprintf(stderr, "NODE %s \n", GetAstNodeTypeInfo(AstNode.GetNodeType())._EnumeratorName);
-
         const Vst::Node* VstNode;
         RsSemanticTokenKind OutTokenKind;
 
         switch (AstNode.GetNodeType()) {
         case EAstNodeType::Literal_Path:
-        case EAstNodeType::Identifier_Module:
         case EAstNodeType::Identifier_ModuleAlias:
             OutTokenKind = RsSemanticTokenKind::NAMESPACE;
             break;
         case EAstNodeType::Identifier_BuiltInMacro:
             OutTokenKind = RsSemanticTokenKind::MACRO;
             break;
-        case EAstNodeType::Identifier_Enum:
-            OutTokenKind = RsSemanticTokenKind::ENUM;
+        case EAstNodeType::Identifier_OverloadedFunction:
+            OutTokenKind = RsSemanticTokenKind::FUNCTION;
             break;
+        case EAstNodeType::Identifier_Module:
+        case EAstNodeType::Identifier_Enum:
         case EAstNodeType::Identifier_Class:
-            OutTokenKind = RsSemanticTokenKind::CLASS;
-            break;
         case EAstNodeType::Identifier_Interface:
-            OutTokenKind = RsSemanticTokenKind::INTERFACE;
-            break;
         case EAstNodeType::Identifier_Function:
-        case EAstNodeType::Identifier_OverloadedFunction:
-            OutTokenKind = RsSemanticTokenKind::FUNCTION;
+            if (!ResolveIdentifier(AstNode, _Program)) {
+                goto visit_all;
+            }
+            OutTokenKind = IdentifierTokenKind(AstNode.GetNodeType());
             break;
         case EAstNodeType::Literal_String:
         case EAstNodeType::Literal_Char:
@@ -83,15 +87,28 @@ public:
     }
 
 private:
-    void VisitMacroCall(const CExprMacroCall& MacroCall) {
-        const auto& MacroIdentifier = static_cast<CExprIdentifierBuiltInMacro&>(*MacroCall.Name());
-        const CSymbol MacroSymbol = MacroIdentifier._Symbol;
+    void VisitMacroCall(const CExprMacroCall& /*MacroCall*/) {
+        // TODO: Tokens for built-in macro calls, use `verseCE/syntaxTree` to inspect their AST
+    }
 
-        fprintf(stderr, "Macro symbol : %s\n", MacroSymbol.AsCString());
+    static RsSemanticTokenKind IdentifierTokenKind(EAstNodeType NodeType) {
+        switch (NodeType) {
+        case EAstNodeType::Identifier_Module:
+            return RsSemanticTokenKind::NAMESPACE;
+        case EAstNodeType::Identifier_Enum:
+            return RsSemanticTokenKind::ENUM;
+        case EAstNodeType::Identifier_Class:
+            return RsSemanticTokenKind::CLASS;
+        case EAstNodeType::Identifier_Interface:
+            return RsSemanticTokenKind::INTERFACE;
+        default:
+            return RsSemanticTokenKind::FUNCTION;
+        }
     }
 
 private:
     RsSemanticTokensAccumulator* _TokenAccumulator;
+    const CSemanticProgram& _Program;
 
     void EmitToken(const Vst::Node* OriginNode, RsSemanticTokenKind TokenKind) {
         RsSemanticTokenEntry TokenEntry = {
@@ -206,6 +223,29 @@ private:
     }
 };
 
+const CDefinition* ResolveIdentifier(const CAstNode& AstNode, const CSemanticProgram& Program) {
+    switch (AstNode.GetNodeType()) {
+    case EAstNodeType::Identifier_Data:
+        return &static_cast<const CExprIdentifierData&>(AstNode)._DataDefinition;
+    case EAstNodeType::Identifier_Function:
+        return &static_cast<const CExprIdentifierFunction&>(AstNode)._Function;
+    case EAstNodeType::Identifier_TypeAlias:
+        return &static_cast<const CExprIdentifierTypeAlias&>(AstNode)._TypeAlias;
+    case EAstNodeType::Identifier_Module:
+        return static_cast<const CExprIdentifierModule&>(AstNode).GetModule(Program);
+    case EAstNodeType::Identifier_Class: {
+        const CClass* Class = static_cast<const CExprIdentifierClass&>(AstNode).GetClass(Program);
+        return Class ? Class->Definition() : nullptr;
+    }
+    case EAstNodeType::Identifier_Interface:
+        return static_cast<const CExprInterfaceType&>(AstNode).GetInterface(Program);
+    case EAstNodeType::Identifier_Enum:
+        return static_cast<const CExprEnumerationType&>(AstNode).GetEnumeration(Program);
+    default:
+        return nullptr;
+    }
+}
+
 } // namespace Verse::LspCE
 
 extern "C" void Lsp_SemanticTokens(
@@ -214,12 +254,8 @@ extern "C" void Lsp_SemanticTokens(
     const char* Path,
     RsSemanticTokensAccumulator* TokenAccumulator
 ) {
-    const Vst::Project& ProjectVst = *ProjectContainer->_BuildManager.GetProjectVst();
-
-    CUTF8String SnippetPath = uLang::FilePathUtils::NormalizePath(CUTF8String(Path));
-    const Vst::Snippet* SnippetVst = ProjectVst.FindSnippetByFilePath(SnippetPath);
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
     if (!SnippetVst) {
-        fprintf(stderr, "\nCould not find snipppet\n\n");
         return;
     }
 
@@ -228,7 +264,7 @@ extern "C" void Lsp_SemanticTokens(
         CVstSemanticTokensVisitor VstVisitor(TokenAccumulator, false);
         VstVisitor.Visit(*SnippetVst);
 
-        CSemanticTokensVisitor AstVisitor(TokenAccumulator, *ProjectContainer->_Symbols);
+        CSemanticTokensVisitor AstVisitor(TokenAccumulator, *ProjectContainer->_Symbols, *ProjectContainer->_ProgramContext->_Program);
         AstVisitor.VisitAll(*AstNode);
     } else {
         // TODO: Remove the fallback because Ast always seem to parse if Vst does
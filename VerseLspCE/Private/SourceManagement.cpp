@@ -43,13 +43,7 @@ struct FFI_PackageSettings {
     bool _bAllowExperimental;
 };
 
-extern "C" const CSourceProject::SPackage* Lsp_RegisterPackage(
-    LspProjectContainer* ProjectContainer,
-    const char* PackageName,
-    const char* DirPath,
-    const bool bReadOnly,
-    FFI_PackageSettings Settings
-) {
+static CSourcePackage::SSettings ToPackageSettings(const FFI_PackageSettings& Settings) {
     uLang::TArray<CUTF8String> DependencyPackages;
     for (size_t Index = 0; Index < Settings._DependencyPackagesLen; Index++) {
         DependencyPackages.Add(CUTF8String(Settings._DependencyPackages[Index]));
@@ -72,13 +66,36 @@ extern "C" const CSourceProject::SPackage* Lsp_RegisterPackage(
     if (Settings._VniDestDir) {
         PackageSettings._VniDestDir = uLang::TOptional(CUTF8String(Settings._VniDestDir));
     }
+    return PackageSettings;
+}
+
+extern "C" const CSourceProject::SPackage* Lsp_RegisterPackage(
+    LspProjectContainer* ProjectContainer,
+    const char* PackageName,
+    const char* DirPath,
+    const bool bReadOnly,
+    FFI_PackageSettings Settings
+) {
+    // Rust holds pointers to packages, which regrowing the array would invalidate
+    const TArray<CSourceProject::SPackage>& Packages = ProjectContainer->_Project->_Packages;
+    if (Packages.Num() >= Packages.Max()) {
+        return nullptr;
+    }
 
     const auto& NewPackage = RegisterPackage(ProjectContainer->_Project,
-            CUTF8String(PackageName), CUTF8String(DirPath), bReadOnly, PackageSettings);
+            CUTF8String(PackageName), CUTF8String(DirPath), bReadOnly, ToPackageSettings(Settings));
 
     return &NewPackage;
 }
 
+extern "C" void Lsp_UpdatePackageSettings(
+    const CSourceProject::SPackage* Package,
+    FFI_PackageSettings Settings
+) {
+    // picked up by the next build, which compiles all packages again
+    Package->_Package->_Settings = ToPackageSettings(Settings);
+}
+
 extern "C" void Lsp_UnregisterPackage(
     const CSourceProject::SPackage* Package
 ) {
@@ -123,3 +140,27 @@ extern "C" void Lsp_UpsertSource(
     Module->AddSnippet(NewSnippet);
 }
 
+
+static bool RemoveSnippet(const TSRef<CSourceModule>& Module, const CUTF8String& SnippetPath) {
+    auto Snippet = Module->_SourceSnippets.FindByPredicate([&SnippetPath](ISourceSnippet* Candidate) -> bool {
+        return Candidate->GetPath() == SnippetPath;
+    });
+    if (Snippet) {
+        Module->_SourceSnippets.Remove(*Snippet);
+        return true;
+    }
+
+    for (const TSRef<CSourceModule>& Submodule : Module->_Submodules) {
+        if (RemoveSnippet(Submodule, SnippetPath)) {
+            return true;
+        }
+    }
+    return false;
+}
+
+extern "C" void Lsp_RemoveSource(
+    const CSourceProject::SPackage* Package,
+    const char* Path
+) {
+    RemoveSnippet(Package->_Package->_RootModule, CUTF8String(Path));
+}
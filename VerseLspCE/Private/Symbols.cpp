@@ -0,0 +1,302 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Semantics/Expression.h"
+#include "uLang/Semantics/SemanticClass.h"
+#include "uLang/Semantics/SemanticEnumeration.h"
+#include "uLang/Semantics/SemanticFunction.h"
+#include "uLang/Semantics/SemanticInterface.h"
+#include "uLang/Semantics/SemanticTypes.h"
+#include "uLang/Semantics/TypeAlias.h"
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+static const CDefinition* GetTypeDefinition(const CTypeBase* Type) {
+    if (!Type) {
+        return nullptr;
+    }
+
+    const CNormalType& NormalType = Type->GetNormalType();
+    // `?t` and `var t` data go to `t`
+    if (const COptionType* OptionType = NormalType.AsNullable<COptionType>()) {
+        return GetTypeDefinition(OptionType->GetValueType());
+    }
+    if (const CPointerType* PointerType = NormalType.AsNullable<CPointerType>()) {
+        return GetTypeDefinition(PointerType->PositiveValueType());
+    }
+
+    if (const CClass* Class = NormalType.AsNullable<CClass>()) {
+        return Class->Definition();
+    }
+    if (const CInterface* Interface = NormalType.AsNullable<CInterface>()) {
+        return Interface;
+    }
+    if (const CEnumeration* Enumeration = NormalType.AsNullable<CEnumeration>()) {
+        return Enumeration;
+    }
+    return nullptr;
+}
+
+class CSymbolsVisitor final : public SAstVisitor {
+public:
+    CSymbolsVisitor(RsSymbolAccumulator* SymbolAccumulator, const CSemanticProgram& Program)
+        : _SymbolAccumulator(SymbolAccumulator)
+        , _Program(Program)
+        {}
+
+    virtual void Visit(const char* /*FieldName*/, CAstNode& AstNode) override {
+        VisitElement(AstNode);
+    }
+
+    virtual void VisitElement(CAstNode& AstNode) override {
+        switch (AstNode.GetNodeType()) {
+        case EAstNodeType::Definition_Module: {
+            const CModule* Module = static_cast<CExprModuleDefinition&>(AstNode)._SemanticModule;
+            if (Module) {
+                EmitDefinition(AstNode, *Module, RsDefinitionKind::Module);
+            }
+            break;
+        }
+        case EAstNodeType::Definition_Class: {
+            const CClassDefinition& Class = static_cast<CExprClassDefinition&>(AstNode)._Class;
+            TArray<CUTF8String> Supertypes;
+            if (Class._Superclass) {
+                Supertypes.Add(GetQualifiedName(*Class._Superclass->Definition()));
+            }
+            for (const CInterface* SuperInterface : Class._SuperInterfaces) {
+                Supertypes.Add(GetQualifiedName(*SuperInterface));
+            }
+            EmitDefinition(AstNode, Class,
+                    Class._StructOrClass == EStructOrClass::Struct ? RsDefinitionKind::Struct : RsDefinitionKind::Class,
+                    Supertypes);
+            break;
+        }
+        case EAstNodeType::Definition_Interface: {
+            const CInterface& Interface = static_cast<CExprInterfaceDefinition&>(AstNode)._Interface;
+            TArray<CUTF8String> Supertypes;
+            for (const CInterface* SuperInterface : Interface._SuperInterfaces) {
+                Supertypes.Add(GetQualifiedName(*SuperInterface));
+            }
+            EmitDefinition(AstNode, Interface, RsDefinitionKind::Interface, Supertypes);
+            break;
+        }
+        case EAstNodeType::Definition_Enum:
+            EmitDefinition(AstNode, static_cast<CExprEnumDefinition&>(AstNode)._Enum, RsDefinitionKind::Enum);
+            break;
+        case EAstNodeType::Definition_Function: {
+            const CFunction& Function = *static_cast<CExprFunctionDefinition&>(AstNode)._Function;
+            EmitDefinition(AstNode, Function, RsDefinitionKind::Function);
+
+            // references within the body are calls made by this function
+            const CFunction* PrevEnclosingFunction = _EnclosingFunction;
+            _EnclosingFunction = &Function;
+            VisitAll(AstNode);
+            _EnclosingFunction = PrevEnclosingFunction;
+            return;
+        }
+        case EAstNodeType::Definition_Data: {
+            const CExprDataDefinition& DataDefinition = static_cast<CExprDataDefinition&>(AstNode);
+            const CDataDefinition& DataMember = *DataDefinition._DataMember;
+            EmitDefinition(AstNode, DataMember, RsDefinitionKind::Data, {}, GetTypeDefinition(DataMember.GetType()),
+                    DataDefinition.Value().IsValid());
+            break;
+        }
+        case EAstNodeType::Definition_TypeAlias:
+            EmitDefinition(AstNode, *static_cast<CExprTypeAliasDefinition&>(AstNode)._TypeAlias, RsDefinitionKind::TypeAlias);
+            break;
+
+        case EAstNodeType::Assignment: {
+            // `set X = ...` only writes to the assigned identifier, receivers and indices
+            // of e.g `set Obj.X[I] = ...` are read
+            const CExprAssignment& Assignment = static_cast<CExprAssignment&>(AstNode);
+            if (Assignment.Lhs()) {
+                const CAstNode* PrevWriteTarget = _WriteTarget;
+                _WriteTarget = FindAssignedIdentifier(*Assignment.Lhs());
+                VisitElement(*Assignment.Lhs());
+                _WriteTarget = PrevWriteTarget;
+            }
+            if (Assignment.Rhs()) {
+                VisitElement(*Assignment.Rhs());
+            }
+            return;
+        }
+
+        case EAstNodeType::Invoke_Invocation:
+            _Callee = static_cast<CExprInvocation&>(AstNode).GetCallee().Get();
+            break;
+
+        default:
+            if (const CDefinition* Definition = ResolveIdentifier(AstNode, _Program)) {
+                EmitReference(AstNode, *Definition);
+            }
+            break;
+        }
+
+        VisitAll(AstNode);
+    }
+
+    void VisitAll(const CAstNode& AstNode) {
+        AstNode.VisitImmediates(*this);
+        AstNode.VisitChildren(*this);
+    }
+
+private:
+    RsSymbolAccumulator* _SymbolAccumulator;
+    const CSemanticProgram& _Program;
+
+    const CAstNode* _WriteTarget = nullptr;
+    const CAstNode* _Callee = nullptr;
+    const CFunction* _EnclosingFunction = nullptr;
+
+    static const CAstNode* FindAssignedIdentifier(const CExpressionBase& Lhs) {
+        const CExpressionBase* Expression = &Lhs;
+        while (Expression) {
+            switch (Expression->GetNodeType()) {
+                case EAstNodeType::Identifier_Data:
+                    return Expression;
+                case EAstNodeType::Invoke_Invocation:
+                    // indexing, `Arr[I]` assigns an element of `Arr`
+                    Expression = static_cast<const CExprInvocation*>(Expression)->GetCallee().Get();
+                    break;
+                case EAstNodeType::Invoke_PointerToReference:
+                    Expression = static_cast<const CExprPointerToReference*>(Expression)->Operand().Get();
+                    break;
+                default:
+                    return nullptr;
+            }
+        }
+        return nullptr;
+    }
+
+    void EmitDefinition(
+        const CAstNode& AstNode,
+        const CDefinition& Definition,
+        RsDefinitionKind Kind,
+        const TArray<CUTF8String>& Supertypes = {},
+        const CDefinition* TypeDefinition = nullptr,
+        bool bInitialized = false
+    ) {
+        const Vst::Node* VstNode = AstNode.GetMappedVstNode();
+        if (!VstNode) {
+            return;
+        }
+
+        const CUTF8String QualifiedName = GetQualifiedName(Definition);
+        const CUTF8String ContainerName = Definition._EnclosingScope.GetScopePath('/', CScope::EPathMode::PrefixSeparator);
+        const CDefinition* OverriddenDefinition = Definition.GetOverriddenDefinition();
+        const CUTF8String OverriddenQualifiedName = OverriddenDefinition ? GetQualifiedName(*OverriddenDefinition) : CUTF8String();
+        const CUTF8String TypeQualifiedName = TypeDefinition ? GetQualifiedName(*TypeDefinition) : CUTF8String();
+        TArray<const char*> SupertypeNames;
+        for (const CUTF8String& Supertype : Supertypes) {
+            SupertypeNames.Add(Supertype.AsCString());
+        }
+
+        RsSymbolDefinition SymbolDefinition = {
+            ._QualifiedName = QualifiedName.AsCString(),
+            ._Name = Definition.AsNameCString(),
+            ._ContainerName = ContainerName.AsCString(),
+            ._Kind = Kind,
+            ._Span = TextRangeToSpan(VstNode->Whence()),
+            ._NameSpan = TextRangeToSpan(FindIdentifierNode(*VstNode, Definition.AsNameStringView()).Whence()),
+            ._Supertypes = SupertypeNames.GetData(),
+            ._SupertypesLen = size_t(SupertypeNames.Num()),
+            ._OverriddenQualifiedName = OverriddenDefinition ? OverriddenQualifiedName.AsCString() : nullptr,
+            ._TypeQualifiedName = TypeDefinition ? TypeQualifiedName.AsCString() : nullptr,
+            ._bInitialized = bInitialized,
+        };
+        // AddSymbolDefinition creates owned Rust strings from char* pointers
+        RS_AddSymbolDefinition(_SymbolAccumulator, SymbolDefinition);
+    }
+
+    void EmitReference(const CAstNode& AstNode, const CDefinition& Definition) {
+        const Vst::Node* VstNode = AstNode.GetMappedVstNode();
+        if (!VstNode) {
+            return;
+        }
+
+        const CUTF8String QualifiedName = GetQualifiedName(Definition);
+        const CUTF8String CallerQualifiedName = _EnclosingFunction ? GetQualifiedName(*_EnclosingFunction) : CUTF8String();
+
+        RsSymbolReference SymbolReference = {
+            ._QualifiedName = QualifiedName.AsCString(),
+            ._Span = TextRangeToSpan(FindIdentifierNode(*VstNode, Definition.AsNameStringView()).Whence()),
+            ._bWrite = &AstNode == _WriteTarget,
+            ._bCall = &AstNode == _Callee,
+            ._CallerQualifiedName = _EnclosingFunction ? CallerQualifiedName.AsCString() : nullptr,
+        };
+        RS_AddSymbolReference(_SymbolAccumulator, SymbolReference);
+    }
+};
+
+} // namespace Verse::LspCE
+
+namespace Verse::LspCE
+{
+
+static const Vst::Node* FindIdentifierNodeRecursive(const Vst::Node& Node, const CUTF8StringView& Name) {
+    if (const Vst::Identifier* Identifier = Node.AsNullable<Vst::Identifier>()) {
+        if (Identifier->GetSourceText() == Name) {
+            return &Node;
+        }
+    }
+    for (const auto& Child : Node.GetChildren()) {
+        if (const Vst::Node* Found = FindIdentifierNodeRecursive(*Child, Name)) {
+            return Found;
+        }
+    }
+    return nullptr;
+}
+
+CUTF8String GetQualifiedName(const CDefinition& Definition) {
+    const CUTF8String ScopePath = Definition._EnclosingScope.GetScopePath('/', CScope::EPathMode::PrefixSeparator);
+
+    // overloaded functions share their path, and so do same-named locals of sibling blocks,
+    // tell them apart by where they are defined
+    const CScope::EKind ScopeKind = Definition._EnclosingScope.GetKind();
+    const bool bSharesPath = Definition.GetKind() == CDefinition::EKind::Function
+        || ScopeKind == CScope::EKind::Function
+        || ScopeKind == CScope::EKind::ControlScope;
+    if (bSharesPath) {
+        const CAstNode* AstNode = Definition.GetAstNode();
+        const Vst::Node* VstNode = AstNode ? AstNode->GetMappedVstNode() : nullptr;
+        if (VstNode) {
+            const STextRange Range = VstNode->Whence();
+            return CUTF8String("%s.%s@%u:%u", ScopePath.AsCString(), Definition.AsNameCString(),
+                    Range.BeginRow(), Range.BeginColumn());
+        }
+    }
+    return CUTF8String("%s.%s", ScopePath.AsCString(), Definition.AsNameCString());
+}
+
+const Vst::Node& FindIdentifierNode(const Vst::Node& Node, const CUTF8StringView& Name) {
+    // Mapped nodes may span more than the identifier itself,
+    // e.g `var X<public>:int = 0`, `Obj.X` or `F(Arg:int)<suspends>:void =`
+    const Vst::Node* Found = FindIdentifierNodeRecursive(Node, Name);
+    return Found ? *Found : Node;
+}
+
+} // namespace Verse::LspCE
+
+extern "C" bool Lsp_CollectSymbols(
+    LspProjectContainer* ProjectContainer,
+    const char* Path,
+    RsSymbolAccumulator* SymbolAccumulator
+) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
+    if (!SnippetVst) {
+        return false;
+    }
+
+    const CAstNode* AstNode = SnippetVst->GetMappedAstNode();
+    if (!AstNode) {
+        return false;
+    }
+
+    CSymbolsVisitor Visitor(SymbolAccumulator, *ProjectContainer->_ProgramContext->_Program);
+    Visitor.VisitAll(*AstNode);
+    return true;
+}
@@ -0,0 +1,111 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+class CVstSyntaxTreeVisitor final {
+public:
+    CVstSyntaxTreeVisitor(RsSyntaxTreeAccumulator* SyntaxTreeAccumulator)
+        : _SyntaxTreeAccumulator(SyntaxTreeAccumulator)
+        {}
+
+    void Visit(const Vst::Node& Node, uint32_t Depth = 0) {
+        CUTF8String Text;
+        if (const Vst::Identifier* Identifier = Node.AsNullable<Vst::Identifier>()) {
+            Text = Identifier->GetSourceText();
+        }
+
+        RsSyntaxNode SyntaxNode = {
+            ._Tree = RsSyntaxTreeKind::VST,
+            ._Depth = Depth,
+            ._Kind = Vst::GetNodeTypeName(Node.GetElementType()),
+            ._Field = nullptr,
+            ._Text = Text.IsEmpty() ? nullptr : Text.AsCString(),
+            ._bHasSpan = true,
+            ._Span = TextRangeToSpan(Node.Whence()),
+        };
+        // AddSyntaxNode creates owned Rust strings from char* pointers
+        RS_AddSyntaxNode(_SyntaxTreeAccumulator, SyntaxNode);
+
+        for (const auto& Child : Node.GetPrefixComments()) {
+            Visit(*Child, Depth + 1);
+        }
+        for (const auto& Child : Node.GetChildren()) {
+            Visit(*Child, Depth + 1);
+        }
+        for (const auto& Child : Node.GetPostfixComments()) {
+            Visit(*Child, Depth + 1);
+        }
+    }
+
+private:
+    RsSyntaxTreeAccumulator* _SyntaxTreeAccumulator;
+};
+
+class CAstSyntaxTreeVisitor final : public SAstVisitor {
+public:
+    CAstSyntaxTreeVisitor(RsSyntaxTreeAccumulator* SyntaxTreeAccumulator)
+        : _SyntaxTreeAccumulator(SyntaxTreeAccumulator)
+        {}
+
+    virtual void Visit(const char* FieldName, CAstNode& AstNode) override {
+        EmitNode(FieldName, AstNode);
+    }
+
+    virtual void VisitElement(CAstNode& AstNode) override {
+        EmitNode(nullptr, AstNode);
+    }
+
+    void EmitNode(const char* FieldName, const CAstNode& AstNode) {
+        const Vst::Node* VstNode = AstNode.GetMappedVstNode();
+
+        RsSyntaxNode SyntaxNode = {
+            ._Tree = RsSyntaxTreeKind::AST,
+            ._Depth = _Depth,
+            ._Kind = GetAstNodeTypeInfo(AstNode.GetNodeType())._EnumeratorName,
+            ._Field = FieldName,
+            ._Text = nullptr,
+            ._bHasSpan = VstNode != nullptr,
+            ._Span = VstNode ? TextRangeToSpan(VstNode->Whence()) : RsSourceSpan{},
+        };
+        // AddSyntaxNode creates owned Rust strings from char* pointers
+        RS_AddSyntaxNode(_SyntaxTreeAccumulator, SyntaxNode);
+
+        ++_Depth;
+        AstNode.VisitImmediates(*this);
+        AstNode.VisitChildren(*this);
+        --_Depth;
+    }
+
+private:
+    RsSyntaxTreeAccumulator* _SyntaxTreeAccumulator;
+
+    uint32_t _Depth = 0;
+};
+
+} // namespace Verse::LspCE
+
+extern "C" void Lsp_SyntaxTree(
+    LspProjectContainer* ProjectContainer,
+    const char* Path,
+    RsSyntaxTreeAccumulator* SyntaxTreeAccumulator
+) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
+    if (!SnippetVst) {
+        return;
+    }
+
+    CVstSyntaxTreeVisitor VstVisitor(SyntaxTreeAccumulator);
+    VstVisitor.Visit(*SnippetVst);
+
+    // the AST is only mapped once the snippet went through semantic analysis
+    if (const CAstNode* AstNode = SnippetVst->GetMappedAstNode()) {
+        CAstSyntaxTreeVisitor AstVisitor(SyntaxTreeAccumulator);
+        AstVisitor.EmitNode(nullptr, *AstNode);
+    }
+}
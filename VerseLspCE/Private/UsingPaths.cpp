@@ -0,0 +1,74 @@
+#include "VerseLspCE.hpp"
+
+#include "uLang/Syntax/VstNode.h"
+
+using namespace Verse;
+using namespace Verse::LspCE;
+
+namespace Verse::LspCE
+{
+
+class CVstUsingPathsVisitor final {
+public:
+    CVstUsingPathsVisitor(RsUsingPathsAccumulator* UsingPathsAccumulator)
+        : _UsingPathsAccumulator(UsingPathsAccumulator)
+        {}
+
+    void Visit(const Vst::Node& Node) {
+        if (const Vst::Macro* MacroNode = Node.AsNullable<Vst::Macro>()) {
+            if (const Vst::Identifier* MacroIdentifier = MacroNode->GetName()->AsNullable<Vst::Identifier>()) {
+                if (MacroIdentifier->GetSourceText() == "using") {
+                    for (const auto& Child : Node.GetChildren()) {
+                        if (&*Child != &*MacroNode->GetName()) {
+                            VisitUsing(*Child);
+                        }
+                    }
+                    return;
+                }
+            }
+        }
+
+        for (const auto& Child : Node.GetChildren()) {
+            Visit(*Child);
+        }
+    }
+
+private:
+    RsUsingPathsAccumulator* _UsingPathsAccumulator;
+
+    void VisitUsing(const Vst::Node& Node) {
+        // `/Verse.org/Simulation`, or relative to the enclosing modules e.g `Sibling`
+        const Vst::PathLiteral* PathLiteral = Node.AsNullable<Vst::PathLiteral>();
+        const Vst::Identifier* Identifier = Node.AsNullable<Vst::Identifier>();
+        if (PathLiteral || Identifier) {
+            const CUTF8String Path = PathLiteral ? PathLiteral->GetSourceText() : Identifier->GetSourceText();
+            RsUsingPath UsingPath = {
+                ._Path = Path.AsCString(),
+                ._Span = TextRangeToSpan(Node.Whence()),
+            };
+            // AddUsingPath creates owned Rust strings from char* pointers
+            RS_AddUsingPath(_UsingPathsAccumulator, UsingPath);
+            return;
+        }
+
+        for (const auto& Child : Node.GetChildren()) {
+            VisitUsing(*Child);
+        }
+    }
+};
+
+} // namespace Verse::LspCE
+
+extern "C" void Lsp_UsingPaths(
+    LspProjectContainer* ProjectContainer,
+    const char* Path,
+    RsUsingPathsAccumulator* UsingPathsAccumulator
+) {
+    const Vst::Snippet* SnippetVst = FindSnippetVst(*ProjectContainer, Path);
+    if (!SnippetVst) {
+        return;
+    }
+
+    CVstUsingPathsVisitor Visitor(UsingPathsAccumulator);
+    Visitor.Visit(*SnippetVst);
+}
@@ -1,6 +1,7 @@
 #include "VerseLspCE.hpp"
 
 #include "ULangUE.h"
+#include "uLang/Common/Text/FilePathUtils.h"
 #include "uLang/Toolchain/ModularFeatureManager.h"
 #include "uLang/Parser/ParserPass.h"
 #include "uLang/SemanticAnalyzer/SemanticAnalyzerPass.h"
@@ -40,13 +41,25 @@ extern "C" LspProjectContainer* Lsp_RegisterProjectContainer(
     };
 
     // NOTE: Arbitrary limit of packages, that if exceeded would cause package pointers to be fucked by regrowth
-    //       In practice the number of packages with a vproject should always be below that number for now
+    //       In practice the number of packages with a vproject should always be below that number for now,
+    //       Lsp_RegisterPackage refuses packages beyond it
     // TODO: A better way to handle Rust references to packages (maybe Project* + index?)
     ProjectContainer->_Project->_Packages.Reserve(16);
 
     return ProjectContainer;
 }
 
+extern "C" void Lsp_UnregisterProjectContainer(
+    LspProjectContainer* ProjectContainer
+) {
+    CProgramBuildManager* BuildManager = &ProjectContainer->_BuildManager;
+    if (ProjectContainer->_ProgramContext) {
+        delete ProjectContainer->_ProgramContext;
+    }
+    delete ProjectContainer;
+    delete BuildManager;
+}
+
 
 extern "C" void Lsp_Build(
     LspProjectContainer* ProjectContainer,
@@ -96,6 +109,10 @@ extern "C" void Lsp_Build(
             ._Span = TextRangeToSpan(Range),
         };
 
+        SDiagnosticContext Context;
+        CollectDiagnosticContext(*ProjectContainer, *Glitch, Context);
+        Diagnostic._Context = Context._Context;
+
         int32_t SeverityCode = 0;
         switch (GlitchInfo.Severity) {
             case EDiagnosticSeverity::Error: SeverityCode = 1; break;
@@ -123,5 +140,12 @@ RsSourceSpan TextRangeToSpan(STextRange Range) {
     };
 }
 
+const Vst::Snippet* FindSnippetVst(const LspProjectContainer& ProjectContainer, const char* Path) {
+    const Vst::Project& ProjectVst = *ProjectContainer._BuildManager.GetProjectVst();
+
+    CUTF8String SnippetPath = uLang::FilePathUtils::NormalizePath(CUTF8String(Path));
+    return ProjectVst.FindSnippetByFilePath(SnippetPath);
+}
+
 } // namespace Verse::LspCE
 
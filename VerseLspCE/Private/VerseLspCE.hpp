@@ -4,6 +4,7 @@
 
 
 #include "uLang/Toolchain/ProgramBuildManager.h"
+#include "uLang/Syntax/VstNode.h"
 
 
 namespace Verse::LspCE {
@@ -22,5 +23,23 @@ struct LspProjectContainer {
 
 RsSourceSpan TextRangeToSpan(STextRange Range);
 
+// Owns the strings an `RsDiagnosticContext` points to
+struct SDiagnosticContext {
+    CUTF8String _Identifier;
+    CUTF8String _MissingEffects;
+    RsDiagnosticContext _Context = {};
+};
+
+void CollectDiagnosticContext(const LspProjectContainer& ProjectContainer, const SGlitch& Glitch, SDiagnosticContext& OutContext);
+
+const Vst::Snippet* FindSnippetVst(const LspProjectContainer& ProjectContainer, const char* Path);
+
+// Definition an identifier resolves to, shared by semantic tokens and symbol references
+// so that both agree on what an identifier is
+const CDefinition* ResolveIdentifier(const CAstNode& AstNode, const CSemanticProgram& Program);
+
+CUTF8String GetQualifiedName(const CDefinition& Definition);
+const Vst::Node& FindIdentifierNode(const Vst::Node& Node, const CUTF8StringView& Name);
+
 } // namespace Verse::LspCE
 
@@ -14,12 +14,36 @@ extern "C" {
     // Diagnostics {{{
     struct RsDiagnosticAccumulator;
 
+    // Reference codes of the diagnostics quick fixes are offered for
+    enum RsReferenceCode : uint16_t {
+        // "Unknown identifier"
+        UnknownIdentifierReferenceCode = 3506,
+        // "This invocation calls a function that has the '...' effect, which is not allowed by its context"
+        EffectNotAllowedReferenceCode = 3512,
+    };
+
+    // Compiler context used to build quick fixes
+    struct RsDiagnosticContext {
+        // Identifier at the diagnostic location, nullptr if none
+        const char* _Identifier;
+        // Space-separated effect specifiers missing from the enclosing function, nullptr if none
+        const char* _MissingEffects;
+        // Where effect specifiers of the enclosing function go
+        bool _bHasSpecifiersPosition;
+        uint32_t _SpecifiersRow;
+        uint32_t _SpecifiersColumn;
+        // Expression statement enclosing the diagnostic location, unset for definitions such as `X := Arr[0]`
+        bool _bHasStatementSpan;
+        RsSourceSpan _StatementSpan;
+    };
+
     struct RsDiagnostic {
         const char* _Path;
         const char* _Message;
         uint16_t _ReferenceCode;
         int32_t _Severity;
         RsSourceSpan _Span;
+        RsDiagnosticContext _Context;
     };
 
     void RS_AddDiagnostic(RsDiagnosticAccumulator* DiagnosticAccumulator, RsDiagnostic Diagnostic);
@@ -59,4 +83,159 @@ extern "C" {
 
     void RS_AddSemanticToken(RsSemanticTokensAccumulator* TokenAccumulator, RsSemanticTokenEntry TokenEntry);
     // }}}
+
+    // Symbols {{{
+    struct RsSymbolAccumulator;
+
+    enum class RsDefinitionKind : uint32_t {
+        Module,
+        Class,
+        Struct,
+        Interface,
+        Enum,
+        Function,
+        Data,
+        TypeAlias,
+    };
+
+    struct RsSymbolDefinition {
+        const char* _QualifiedName;
+        const char* _Name;
+        const char* _ContainerName;
+        RsDefinitionKind _Kind;
+        RsSourceSpan _Span;
+        RsSourceSpan _NameSpan;
+        // Qualified names of the parent class and interfaces of classes and interfaces
+        const char** _Supertypes;
+        size_t _SupertypesLen;
+        // Qualified name of the overridden definition, e.g an interface method, nullptr if none
+        const char* _OverriddenQualifiedName;
+        // Qualified name of the class, struct, interface or enum data is of, nullptr if none
+        const char* _TypeQualifiedName;
+        // Data defined with a value, e.g `X:int = 0` rather than `X:int`
+        bool _bInitialized;
+    };
+
+    struct RsSymbolReference {
+        const char* _QualifiedName;
+        RsSourceSpan _Span;
+        bool _bWrite;
+        // Whether the identifier is the callee of an invocation
+        bool _bCall;
+        // Qualified name of the enclosing function, nullptr if none
+        const char* _CallerQualifiedName;
+    };
+
+    void RS_AddSymbolDefinition(RsSymbolAccumulator* SymbolAccumulator, RsSymbolDefinition Definition);
+    void RS_AddSymbolReference(RsSymbolAccumulator* SymbolAccumulator, RsSymbolReference Reference);
+    // }}}
+
+    // Folding Ranges {{{
+    struct RsFoldingRangesAccumulator;
+
+    enum class RsFoldingRangeKind : uint32_t {
+        Region,
+        Comment,
+        Imports,
+    };
+
+    struct RsFoldingRangeEntry {
+        RsFoldingRangeKind _Kind;
+        RsSourceSpan _Span;
+    };
+
+    void RS_AddFoldingRange(RsFoldingRangesAccumulator* FoldingRangesAccumulator, RsFoldingRangeEntry FoldingRangeEntry);
+    // }}}
+
+    // Selection Ranges {{{
+    struct RsSelectionRangeAccumulator;
+
+    void RS_AddSelectionRange(RsSelectionRangeAccumulator* SelectionRangeAccumulator, RsSourceSpan Span);
+    // }}}
+
+    // Inlay Hints {{{
+    struct RsInlayHintsAccumulator;
+
+    enum class RsInlayHintKind : uint32_t {
+        BindingType,
+        IterationType,
+        ParameterName,
+        Effects,
+    };
+
+    struct RsInlayHint {
+        RsInlayHintKind _Kind;
+        const char* _Label;
+        uint32_t _Row;
+        uint32_t _Column;
+    };
+
+    void RS_AddInlayHint(RsInlayHintsAccumulator* InlayHintsAccumulator, RsInlayHint InlayHint);
+    // }}}
+
+    // Formatting {{{
+    struct RsTextEditsAccumulator;
+
+    enum class RsFormattingResult : uint32_t {
+        Formatted,
+        NoSyntaxTree,
+        SyntaxErrors,
+        // The syntax tree doesn't match the source text anymore
+        OutOfDate,
+    };
+
+    struct RsFormattingOptions {
+        uint32_t _IndentWidth;
+        // Indent with tabs rather than _IndentWidth spaces
+        bool _bIndentWithTabs;
+        uint32_t _LineLength;
+        // Only statements within these rows are formatted
+        bool _bHasRange;
+        uint32_t _BeginRow;
+        uint32_t _EndRow;
+    };
+
+    struct RsTextEdit {
+        RsSourceSpan _Span;
+        const char* _NewText;
+    };
+
+    void RS_AddTextEdit(RsTextEditsAccumulator* TextEditsAccumulator, RsTextEdit TextEdit);
+    // }}}
+
+    // Using Paths {{{
+    struct RsUsingPathsAccumulator;
+
+    struct RsUsingPath {
+        // Module path, e.g `/Fortnite.com/Devices`, or relative to the enclosing modules e.g `Devices`
+        const char* _Path;
+        RsSourceSpan _Span;
+    };
+
+    void RS_AddUsingPath(RsUsingPathsAccumulator* UsingPathsAccumulator, RsUsingPath UsingPath);
+    // }}}
+
+    // Syntax Tree {{{
+    struct RsSyntaxTreeAccumulator;
+
+    enum class RsSyntaxTreeKind : uint32_t {
+        VST,
+        AST,
+    };
+
+    // Nodes are emitted depth-first, each tree starting from a root at depth 0
+    struct RsSyntaxNode {
+        RsSyntaxTreeKind _Tree;
+        uint32_t _Depth;
+        const char* _Kind;
+        // Nullable, name of the field the AST node is visited from
+        const char* _Field;
+        // Nullable, source text of identifiers
+        const char* _Text;
+        bool _bHasSpan;
+        RsSourceSpan _Span;
+    };
+
+    void RS_AddSyntaxNode(RsSyntaxTreeAccumulator* SyntaxTreeAccumulator, RsSyntaxNode SyntaxNode);
+    // }}}
 }
@@ -30,6 +30,28 @@ function getBinaryPlatform(): string {
   }
 }
 
+// VS Code settings are camelCase, the server reads snake_case fields like its config files
+function toServerSettings(value: any): any {
+	if (Array.isArray(value)) {
+		return value.map(toServerSettings);
+	}
+	if (value === null || typeof value !== "object") {
+		return value;
+	}
+	const settings: any = {};
+	for (const [key, field] of Object.entries(value)) {
+		const serverKey = key.replace(/[A-Z]/g, letter => `_${letter.toLowerCase()}`);
+		if (key === "projects" && field && typeof field === "object") {
+			// keyed by .vproject file name
+			settings[serverKey] = Object.fromEntries(Object.entries(field)
+				.map(([projectName, projectSettings]) => [projectName, toServerSettings(projectSettings)]));
+		} else {
+			settings[serverKey] = toServerSettings(field);
+		}
+	}
+	return settings;
+}
+
 export function activate(context: vscode.ExtensionContext) {
     const extensionConfig = vscode.workspace.getConfiguration("verseCE");
 
@@ -69,9 +91,24 @@ export function activate(context: vscode.ExtensionContext) {
     vscode.extensions.all
 
 	const clientOptions: LanguageClientOptions = {
-		documentSelector: [{ scheme: "file", language: "verse" }],
+		documentSelector: [
+			{ scheme: "file", language: "verse" },
+			{ scheme: "untitled", language: "verse" },
+			{ scheme: "file", pattern: "**/*.vproject" },
+		],
+		initializationOptions: toServerSettings(JSON.parse(JSON.stringify(extensionConfig))),
 		synchronize: {
-			fileEvents: vscode.workspace.createFileSystemWatcher("**/.verse"),
+			// the server pulls settings of each workspace folder when notified
+			configurationSection: "verseCE",
+			fileEvents: vscode.workspace.createFileSystemWatcher("**/*.verse"),
+		},
+		middleware: {
+			workspace: {
+				configuration: async (params, token, next) => {
+					const result = await next(params, token);
+					return Array.isArray(result) ? result.map(toServerSettings) : result;
+				},
+			},
 		},
 		outputChannel,
 		revealOutputChannelOn: RevealOutputChannelOn.Error,
@@ -85,9 +122,102 @@ export function activate(context: vscode.ExtensionContext) {
 	);
 	client.registerProposedFeatures();
 
+	// code lenses pass plain LSP locations, which the built-in command doesn't accept
+	context.subscriptions.push(vscode.commands.registerCommand(
+		"verseCE.showLocations",
+		(uri: string, position: any, locations: any[]) => {
+			const converter = client.protocol2CodeConverter;
+			return vscode.commands.executeCommand(
+				"editor.action.showReferences",
+				vscode.Uri.parse(uri),
+				converter.asPosition(position),
+				locations.map(location => converter.asLocation(location)),
+			);
+		},
+	));
+
+	// debugging aid, shows the VST and AST the server sees for the active document
+	context.subscriptions.push(vscode.commands.registerCommand(
+		"verseCE.showSyntaxTree",
+		async () => {
+			const editor = vscode.window.activeTextEditor;
+			if (!editor || editor.document.languageId !== "verse") {
+				vscode.window.showWarningMessage("Open a Verse document to show its syntax tree.");
+				return;
+			}
+			const syntaxTree = await client.sendRequest("verseCE/syntaxTree", {
+				textDocument: client.code2ProtocolConverter.asTextDocumentIdentifier(editor.document),
+			});
+			if (!syntaxTree) {
+				vscode.window.showWarningMessage("No syntax tree available for this document yet.");
+				return;
+			}
+			const document = await vscode.workspace.openTextDocument({
+				language: "json",
+				content: JSON.stringify(syntaxTree, null, 2),
+			});
+			await vscode.window.showTextDocument(document, vscode.ViewColumn.Beside);
+		},
+	));
+
+	context.subscriptions.push(vscode.commands.registerCommand(
+		"verseCE.showProjectInfo",
+		async () => {
+			const projectInfo = await client.sendRequest("verseCE/projectInfo", null);
+			const document = await vscode.workspace.openTextDocument({
+				language: "json",
+				content: JSON.stringify(projectInfo, null, 2),
+			});
+			await vscode.window.showTextDocument(document, vscode.ViewColumn.Beside);
+		},
+	));
+
+	const statusBarItem = vscode.window.createStatusBarItem(vscode.StatusBarAlignment.Left);
+	statusBarItem.name = "Verse CE";
+	statusBarItem.command = "verseCE.showProjectInfo";
+	statusBarItem.text = "$(loading~spin) Verse";
+	statusBarItem.show();
+	context.subscriptions.push(statusBarItem);
+
+	let lastBuildText = "$(check) Verse";
+	client.onNotification("verseCE/status", (params: StatusParams) => {
+		const project = params.project;
+		switch (params.status) {
+		case "buildStarted":
+			statusBarItem.text = `$(sync~spin) Verse: building ${project.name}`;
+			statusBarItem.tooltip = project.vprojectUri;
+			break;
+		case "buildFinished": {
+			const { errors, warnings } = project.diagnostics;
+			const icon = errors > 0 ? "$(error)" : warnings > 0 ? "$(warning)" : "$(check)";
+			lastBuildText = `${icon} Verse: ${project.name}`;
+			statusBarItem.text = lastBuildText;
+			statusBarItem.tooltip = `Built ${project.name} in ${project.durationMs}ms`
+				+ ` with ${errors} errors and ${warnings} warnings`;
+			break;
+		}
+		case "busy":
+			statusBarItem.text = `${lastBuildText} $(loading~spin)`;
+			break;
+		case "idle":
+			statusBarItem.text = lastBuildText;
+			break;
+		}
+	});
+
 	client.start();
 }
 
+interface StatusParams {
+	status: "idle" | "busy" | "buildStarted" | "buildFinished";
+	project?: {
+		name: string;
+		vprojectUri: string;
+		durationMs?: number;
+		diagnostics?: { errors: number; warnings: number; information: number; hints: number };
+	};
+}
+
 export function deactivate(): Thenable<void> | undefined {
     const tmpBinaryPath = tmpServerBinaryPath;
     tmpServerBinaryPath = undefined;
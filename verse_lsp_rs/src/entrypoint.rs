@@ -5,15 +5,20 @@ use clap::Parser;
 use lsp_server::{self, Connection, IoThreads, Message};
 
 use lsp_types::{
-    CancelParams, DidChangeWorkspaceFoldersParams, InitializeParams, InitializeResult, OneOf,
+    CallHierarchyServerCapability, CancelParams, DidChangeWorkspaceFoldersParams,
+    FoldingRangeProviderCapability, HoverProviderCapability, ImplementationProviderCapability,
+    InitializeParams, InitializeResult, OneOf, SelectionRangeProviderCapability,
     ServerCapabilities, ServerInfo, TextDocumentSyncCapability, TextDocumentSyncKind,
-    WorkspaceFoldersChangeEvent,
+    TypeDefinitionProviderCapability, WorkspaceFoldersChangeEvent,
 };
 
 use crate::server::VerseLspCESettings;
 use crate::{
     features::{
-        semantic_tokens::capabilities_semantic_tokens, workspace::capabilities_workspace_folders,
+        code_actions::capabilities_code_actions, code_lens::capabilities_code_lens,
+        document_links::capabilities_document_link, rename::capabilities_rename,
+        semantic_tokens::capabilities_semantic_tokens, vproject_file::capabilities_completion,
+        workspace::capabilities_workspace_folders,
     },
     server::{self, LanguageServer, messages::MessageQueue},
 };
@@ -64,11 +69,26 @@ fn server_config() -> InitializeResult {
     let server_capabilities = ServerCapabilities {
         text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
         definition_provider: Some(OneOf::Left(true)),
+        type_definition_provider: Some(TypeDefinitionProviderCapability::Simple(true)),
+        implementation_provider: Some(ImplementationProviderCapability::Simple(true)),
+        references_provider: Some(OneOf::Left(true)),
+        rename_provider: Some(capabilities_rename()),
+        document_highlight_provider: Some(OneOf::Left(true)),
+        folding_range_provider: Some(FoldingRangeProviderCapability::Simple(true)),
+        selection_range_provider: Some(SelectionRangeProviderCapability::Simple(true)),
+        inlay_hint_provider: Some(OneOf::Left(true)),
+        code_action_provider: Some(capabilities_code_actions()),
+        code_lens_provider: Some(capabilities_code_lens()),
+        document_link_provider: Some(capabilities_document_link()),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        call_hierarchy_provider: Some(CallHierarchyServerCapability::Simple(true)),
         // document_symbol_provider: Some(OneOf::Left(true)),
         semantic_tokens_provider: Some(capabilities_semantic_tokens()),
-        // hover_provider: Some(HoverProviderCapability::Simple(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(capabilities_completion()),
         workspace: Some(capabilities_workspace_folders()),
-        // workspace_symbol_provider: Some(OneOf::Left(true)),
+        workspace_symbol_provider: Some(OneOf::Left(true)),
         ..Default::default()
     };
 
@@ -78,33 +98,28 @@ fn server_config() -> InitializeResult {
             name: "VerseLspCE".to_owned(),
             version: Some(env!("CARGO_PKG_VERSION").to_owned()),
         }),
-        ..Default::default()
     }
 }
 
 fn handle_client(connection: Connection, io_threads: IoThreads) -> anyhow::Result<()> {
-    let server_init_payload = serde_json::to_value(server_config())
+    let mut server_init_payload = serde_json::to_value(server_config())
         .context("Couldn't serialize server initialize result")?;
+    // lsp-types 0.95 has no `typeHierarchyProvider` field in `ServerCapabilities`,
+    // so the capability is added to the serialized result instead
+    server_init_payload["capabilities"]["typeHierarchyProvider"] = serde_json::Value::Bool(true);
 
     let (init_id, init_params) = connection.initialize_start()?;
     connection.initialize_finish(init_id, server_init_payload)?;
 
     let client_init_params: InitializeParams =
         serde_json::from_value(init_params).context("Couldn't parse initialize params")?;
-    let mut settings = match client_init_params.initialization_options {
+    let settings: VerseLspCESettings = match client_init_params.initialization_options {
         Some(json) => {
             serde_json::from_value(json).context("Couldn't parse custom VerseLspCE user options")?
         }
         None => VerseLspCESettings::default(),
     };
 
-    // if Fortnite version is not explicitely specified,
-    // we want the actual Latest version rather than whatever the server was compiled with
-    // TODO: Find latest local UEFN version
-    if settings.fortnite_version.is_none() {
-        settings.fortnite_version = Some(3811);
-    }
-
     let connection = Arc::new(connection);
     let message_queue = Arc::new(MessageQueue::new());
 
@@ -113,10 +128,20 @@ fn handle_client(connection: Connection, io_threads: IoThreads) -> anyhow::Resul
         let message_queue = message_queue.clone();
 
         move || {
-            let mut server = LanguageServer::new(connection, message_queue, settings);
-
-            // add default workspace folders
-            if let Some(workspace_folders) = client_init_params.workspace_folders {
+            let mut server = LanguageServer::new(
+                connection,
+                client_init_params.capabilities,
+                message_queue,
+                settings,
+            );
+            server.register_configuration();
+            server.register_workspace_config_watcher();
+
+            let workspace_folders = client_init_params.workspace_folders.unwrap_or_default();
+            server.detect_fortnite_version(&workspace_folders);
+
+            // add default workspace folders, settings are pulled once they are known
+            if !workspace_folders.is_empty() {
                 let _ =
                     server.handle_did_workspace_folders_change(DidChangeWorkspaceFoldersParams {
                         event: WorkspaceFoldersChangeEvent {
@@ -124,6 +149,8 @@ fn handle_client(connection: Connection, io_threads: IoThreads) -> anyhow::Resul
                             ..Default::default()
                         },
                     });
+            } else {
+                server.pull_configuration();
             }
 
             if let Err(err) = server::messages::message_processing_worker(server) {
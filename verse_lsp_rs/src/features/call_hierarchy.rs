@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use fxhash::FxHashSet;
+use lsp_types::*;
+
+use crate::{
+    server::LanguageServer,
+    symbol_index::{DefinitionKind, SymbolDefinition, SymbolIndex},
+};
+
+impl LanguageServer {
+    pub fn handle_req_prepare_call_hierarchy(
+        &self,
+        params: CallHierarchyPrepareParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyItem>>> {
+        let uri = self.normalize_uri(&params.text_document_position_params.text_document.uri)?;
+        let position = params.text_document_position_params.position;
+
+        let mut items = vec![];
+        for symbol in self.resolve_symbol_at(&uri, position) {
+            let symbol_index = &symbol.project_container.symbol_index;
+            for (uri, definition) in symbol_index.find_definitions(&symbol.qualified_name) {
+                if definition.kind == DefinitionKind::Function {
+                    items.push(call_hierarchy_item(symbol_index, uri, definition));
+                }
+            }
+        }
+        dedup_items(&mut items);
+
+        Ok(Some(items))
+    }
+
+    pub fn handle_req_incoming_calls(
+        &self,
+        params: CallHierarchyIncomingCallsParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyIncomingCall>>> {
+        let Some(qualified_name) = item_qualified_name(&params.item) else {
+            return Ok(None);
+        };
+
+        // call sites grouped by caller, packages are shared between project containers
+        let mut calls: BTreeMap<(Url, String), (CallHierarchyItem, Vec<Range>)> = BTreeMap::new();
+        for project_container in self.project_containers.iter() {
+            let symbol_index = &project_container.symbol_index;
+            for (uri, reference) in symbol_index.find_references(qualified_name) {
+                let Some(caller) = reference.caller.as_ref().filter(|_| reference.call) else {
+                    continue;
+                };
+                let Some((caller_uri, caller_definition)) =
+                    symbol_index.find_definitions(caller).next()
+                else {
+                    continue;
+                };
+
+                add_call_site(
+                    &mut calls,
+                    (uri.clone(), caller.clone()),
+                    || call_hierarchy_item(symbol_index, caller_uri, caller_definition),
+                    reference.range,
+                );
+            }
+        }
+
+        Ok(Some(
+            calls
+                .into_values()
+                .map(|(from, from_ranges)| CallHierarchyIncomingCall { from, from_ranges })
+                .collect(),
+        ))
+    }
+
+    pub fn handle_req_outgoing_calls(
+        &self,
+        params: CallHierarchyOutgoingCallsParams,
+    ) -> anyhow::Result<Option<Vec<CallHierarchyOutgoingCall>>> {
+        let Some(qualified_name) = item_qualified_name(&params.item) else {
+            return Ok(None);
+        };
+
+        // call sites grouped by callee
+        let mut calls: BTreeMap<String, (CallHierarchyItem, Vec<Range>)> = BTreeMap::new();
+        for project_container in self.project_containers.iter() {
+            let symbol_index = &project_container.symbol_index;
+            for (uri, reference) in symbol_index.find_calls_from(qualified_name) {
+                // ranges are relative to the caller, which may be defined across several files
+                if *uri != params.item.uri {
+                    continue;
+                }
+                let Some((callee_uri, callee_definition)) = symbol_index
+                    .find_definitions(&reference.qualified_name)
+                    .next()
+                else {
+                    continue;
+                };
+
+                add_call_site(
+                    &mut calls,
+                    reference.qualified_name.clone(),
+                    || call_hierarchy_item(symbol_index, callee_uri, callee_definition),
+                    reference.range,
+                );
+            }
+        }
+
+        Ok(Some(
+            calls
+                .into_values()
+                .map(|(to, from_ranges)| CallHierarchyOutgoingCall { to, from_ranges })
+                .collect(),
+        ))
+    }
+}
+
+/// Drops items found through several project containers sharing a package.
+fn dedup_items(items: &mut Vec<CallHierarchyItem>) {
+    let mut seen = FxHashSet::default();
+    items.retain(|item| seen.insert((item.uri.clone(), item.selection_range)));
+}
+
+/// Adds a call site to the calls grouped by `key`, ignoring call sites already found.
+fn add_call_site<K: Ord>(
+    calls: &mut BTreeMap<K, (CallHierarchyItem, Vec<Range>)>,
+    key: K,
+    item: impl FnOnce() -> CallHierarchyItem,
+    range: Range,
+) {
+    let (_, from_ranges) = calls.entry(key).or_insert_with(|| (item(), vec![]));
+    if !from_ranges.contains(&range) {
+        from_ranges.push(range);
+    }
+}
+
+fn item_qualified_name(item: &CallHierarchyItem) -> Option<&str> {
+    item.data.as_ref()?.as_str()
+}
+
+fn call_hierarchy_item(
+    symbol_index: &SymbolIndex,
+    uri: &Url,
+    definition: &SymbolDefinition,
+) -> CallHierarchyItem {
+    let kind = match symbol_index.enclosing_definition_kind(definition) {
+        Some(DefinitionKind::Class | DefinitionKind::Struct | DefinitionKind::Interface) => {
+            SymbolKind::METHOD
+        }
+        _ => SymbolKind::FUNCTION,
+    };
+
+    CallHierarchyItem {
+        name: definition.name.clone(),
+        kind,
+        tags: None,
+        detail: Some(definition.container_name.clone()),
+        uri: uri.clone(),
+        range: definition.range,
+        selection_range: definition.name_range,
+        // resolved again when expanding incoming/outgoing calls
+        data: Some(definition.qualified_name.clone().into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(line: u32) -> Range {
+        Range::new(Position::new(line, 0), Position::new(line, 4))
+    }
+
+    fn item(name: &str, line: u32) -> CallHierarchyItem {
+        CallHierarchyItem {
+            name: name.to_owned(),
+            kind: SymbolKind::FUNCTION,
+            tags: None,
+            detail: None,
+            uri: Url::parse("file:///project/a.verse").unwrap(),
+            range: range(line),
+            selection_range: range(line),
+            data: None,
+        }
+    }
+
+    #[test]
+    fn dedup_non_adjacent_items() {
+        let mut items = vec![item("F", 0), item("G", 1), item("F", 0)];
+        dedup_items(&mut items);
+        let names: Vec<_> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["F", "G"]);
+    }
+
+    #[test]
+    fn group_call_sites() {
+        let mut calls = BTreeMap::new();
+        add_call_site(&mut calls, "/M.G", || item("G", 5), range(1));
+        add_call_site(&mut calls, "/M.F", || item("F", 4), range(2));
+        add_call_site(&mut calls, "/M.G", || unreachable!(), range(3));
+        // the same call site seen through another project container
+        add_call_site(&mut calls, "/M.G", || unreachable!(), range(1));
+
+        let calls: Vec<_> = calls.into_values().collect();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].0.name, "F");
+        assert_eq!(calls[0].1, [range(2)]);
+        assert_eq!(calls[1].0.name, "G");
+        assert_eq!(calls[1].1, [range(1), range(3)]);
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{BTreeSet, HashMap};
+
+use lsp_types::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{ffi, server::LanguageServer, symbol_index::DefinitionKind};
+
+const CODE_UNKNOWN_IDENTIFIER: i32 = ffi::UNKNOWN_IDENTIFIER_REFERENCE_CODE as i32;
+const CODE_EFFECT_NOT_ALLOWED: i32 = ffi::EFFECT_NOT_ALLOWED_REFERENCE_CODE as i32;
+
+/// Compiler context attached to diagnostics as `data`, used to build quick fixes.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticContext {
+    /// Identifier at the diagnostic location.
+    pub identifier: Option<String>,
+    /// Effect specifiers missing from the enclosing function.
+    pub missing_effects: Vec<String>,
+    /// Where effect specifiers of the enclosing function go.
+    pub specifiers_position: Option<Position>,
+    /// Expression statement enclosing the diagnostic location, none for definitions.
+    pub statement_range: Option<Range>,
+}
+
+pub fn capabilities_code_actions() -> CodeActionProviderCapability {
+    CodeActionProviderCapability::Options(CodeActionOptions {
+        code_action_kinds: Some(vec![CodeActionKind::QUICKFIX]),
+        work_done_progress_options: WorkDoneProgressOptions {
+            work_done_progress: Some(false),
+        },
+        resolve_provider: Some(false),
+    })
+}
+
+impl LanguageServer {
+    pub fn handle_req_code_action(
+        &self,
+        params: CodeActionParams,
+    ) -> anyhow::Result<Option<CodeActionResponse>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let mut actions = vec![];
+        for diagnostic in params.context.diagnostics.iter() {
+            let Some(NumberOrString::Number(code)) = diagnostic.code else {
+                continue;
+            };
+            let Some(context) = diagnostic
+                .data
+                .clone()
+                .and_then(|data| serde_json::from_value::<DiagnosticContext>(data).ok())
+            else {
+                continue;
+            };
+
+            match code {
+                CODE_UNKNOWN_IDENTIFIER => {
+                    self.add_using_quick_fixes(&uri, diagnostic, &context, &mut actions);
+                }
+                CODE_EFFECT_NOT_ALLOWED => {
+                    add_effect_quick_fixes(&uri, diagnostic, &context, &mut actions);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(Some(actions))
+    }
+
+    /// Suggests `using` the modules that define the unknown identifier.
+    fn add_using_quick_fixes(
+        &self,
+        uri: &Url,
+        diagnostic: &Diagnostic,
+        context: &DiagnosticContext,
+        actions: &mut Vec<CodeActionOrCommand>,
+    ) {
+        let Some(identifier) = context.identifier.as_ref() else {
+            return;
+        };
+
+        let mut module_paths = BTreeSet::new();
+        for project_container in self.project_containers.iter() {
+            let symbol_index = &project_container.symbol_index;
+
+            let defined_modules: BTreeSet<String> = symbol_index
+                .definitions()
+                .filter(|(_, definition)| definition.kind == DefinitionKind::Module)
+                .map(|(_, definition)| format!("{}/{}", definition.container_name, definition.name))
+                .chain(
+                    project_container
+                        .packages
+                        .iter()
+                        .map(|package| package.verse_path.clone()),
+                )
+                .collect();
+
+            for (_, definition) in symbol_index.definitions() {
+                // members of classes can't be brought in scope with `using`
+                if definition.name == *identifier
+                    && defined_modules.contains(&definition.container_name)
+                {
+                    module_paths.insert(definition.container_name.clone());
+                }
+            }
+        }
+
+        if module_paths.is_empty() {
+            return;
+        }
+
+        // the open buffer may differ from the file on disk
+        let line = self
+            .project_containers
+            .iter()
+            .find_map(|project_container| project_container.file_cache.get(uri))
+            .map_or(0, |file_state| using_insert_line(&file_state.text));
+        let position = Position::new(line, 0);
+        for module_path in module_paths {
+            let new_text = format!("using {{ {module_path} }}\n");
+            actions.push(quick_fix(
+                format!("Add `using {{ {module_path} }}`"),
+                diagnostic,
+                uri,
+                vec![TextEdit::new(Range::new(position, position), new_text)],
+            ));
+        }
+    }
+}
+
+/// Line new `using` statements go on: after the last top-level `using`,
+/// or after the comments at the top of the file otherwise.
+fn using_insert_line(text: &str) -> u32 {
+    let mut last_using_end = None;
+    let mut leading_comments_end = 0;
+    let mut in_leading_comments = true;
+    let mut in_block_comment = false;
+    let mut in_using_block = false;
+    for (index, line) in text.lines().enumerate() {
+        let next_line = index as u32 + 1;
+        let trimmed = line.trim();
+        if in_block_comment {
+            in_block_comment = !trimmed.contains("#>");
+            if in_leading_comments {
+                leading_comments_end = next_line;
+            }
+            continue;
+        }
+        if in_using_block {
+            in_using_block = !trimmed.contains('}');
+            last_using_end = Some(next_line);
+            continue;
+        }
+
+        if is_using_statement(line) {
+            // `using {` may close on a later line
+            in_using_block = trimmed.contains('{') && !trimmed.contains('}');
+            last_using_end = Some(next_line);
+            in_leading_comments = false;
+        } else if trimmed.starts_with("<#") {
+            in_block_comment = !trimmed.contains("#>");
+            if in_leading_comments {
+                leading_comments_end = next_line;
+            }
+        } else if trimmed.starts_with('#') {
+            if in_leading_comments {
+                leading_comments_end = next_line;
+            }
+        } else if !trimmed.is_empty() {
+            in_leading_comments = false;
+        }
+    }
+    last_using_end.unwrap_or(leading_comments_end)
+}
+
+/// Whether a line starts a top-level `using` statement, rather than e.g `using_count := 0`.
+fn is_using_statement(line: &str) -> bool {
+    line.strip_prefix("using")
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_whitespace() || c == '{'))
+}
+
+/// Suggests adding missing effects to the enclosing function,
+/// or wrapping failable expressions in `if (...)`.
+fn add_effect_quick_fixes(
+    uri: &Url,
+    diagnostic: &Diagnostic,
+    context: &DiagnosticContext,
+    actions: &mut Vec<CodeActionOrCommand>,
+) {
+    if let Some(specifiers_position) = context.specifiers_position {
+        for effect in context.missing_effects.iter() {
+            actions.push(quick_fix(
+                format!("Add `<{effect}>` to the enclosing function"),
+                diagnostic,
+                uri,
+                vec![TextEdit::new(
+                    Range::new(specifiers_position, specifiers_position),
+                    format!("<{effect}>"),
+                )],
+            ));
+        }
+    }
+
+    if context
+        .missing_effects
+        .iter()
+        .any(|effect| effect == "decides")
+        && let Some(statement_range) = context.statement_range
+    {
+        actions.push(quick_fix(
+            "Wrap in `if (...)`".to_owned(),
+            diagnostic,
+            uri,
+            vec![
+                TextEdit::new(
+                    Range::new(statement_range.start, statement_range.start),
+                    "if (".to_owned(),
+                ),
+                TextEdit::new(
+                    Range::new(statement_range.end, statement_range.end),
+                    ") {}".to_owned(),
+                ),
+            ],
+        ));
+    }
+}
+
+fn quick_fix(
+    title: String,
+    diagnostic: &Diagnostic,
+    uri: &Url,
+    edits: Vec<TextEdit>,
+) -> CodeActionOrCommand {
+    CodeActionOrCommand::CodeAction(CodeAction {
+        title,
+        kind: Some(CodeActionKind::QUICKFIX),
+        diagnostics: Some(vec![diagnostic.clone()]),
+        edit: Some(WorkspaceEdit {
+            changes: Some(HashMap::from([(uri.clone(), edits)])),
+            ..Default::default()
+        }),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn using_statements() {
+        assert!(is_using_statement("using { /Verse.org/Simulation }"));
+        assert!(is_using_statement("using{/Verse.org/Simulation}"));
+        assert!(is_using_statement("using /Verse.org/Simulation"));
+        assert!(!is_using_statement("using_count:int = 0"));
+        assert!(!is_using_statement("    using { /Verse.org/Simulation }"));
+    }
+
+    #[test]
+    fn using_insert_lines() {
+        assert_eq!(
+            using_insert_line("using { /A }\nusing { /B }\n\nX := 0\n"),
+            2
+        );
+        assert_eq!(
+            using_insert_line("# header\n<# block\ncomment #>\nX := 0\n"),
+            3
+        );
+        assert_eq!(using_insert_line("using {\n    /A\n}\nX := 0\n"), 3);
+        assert_eq!(using_insert_line("usings := 0\n"), 0);
+    }
+}
@@ -0,0 +1,139 @@
+use fxhash::FxHashSet;
+use lsp_types::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    features::{implementation::collect_implementations, references::reference_locations},
+    server::LanguageServer,
+    symbol_index::DefinitionKind,
+};
+
+/// Client command opening a list of locations, see the VS Code extension.
+const SHOW_LOCATIONS_COMMAND: &str = "verseCE.showLocations";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum CodeLensKind {
+    References,
+    Implementations,
+}
+
+/// Code lenses are counted when resolved, which only happens for visible ones.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CodeLensData {
+    pub uri: Url,
+    pub qualified_name: String,
+    pub kind: CodeLensKind,
+}
+
+pub fn capabilities_code_lens() -> CodeLensOptions {
+    CodeLensOptions {
+        resolve_provider: Some(true),
+    }
+}
+
+impl LanguageServer {
+    pub fn handle_req_code_lens(
+        &self,
+        params: CodeLensParams,
+    ) -> anyhow::Result<Option<Vec<CodeLens>>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let Some(project_container) = self.find_project_container(&uri) else {
+            return Ok(None);
+        };
+        let symbol_index = &project_container.symbol_index;
+        let Some(file_symbols) = symbol_index.file_symbols(&uri) else {
+            return Ok(None);
+        };
+
+        let mut code_lenses = vec![];
+        for definition in file_symbols.definitions.iter() {
+            let implementable = match definition.kind {
+                DefinitionKind::Class | DefinitionKind::Interface => true,
+                DefinitionKind::Function => matches!(
+                    symbol_index.enclosing_definition_kind(definition),
+                    Some(DefinitionKind::Class | DefinitionKind::Interface)
+                ),
+                DefinitionKind::Struct | DefinitionKind::Enum => false,
+                _ => continue,
+            };
+
+            let mut push_code_lens = |kind| {
+                let data = CodeLensData {
+                    uri: uri.clone(),
+                    qualified_name: definition.qualified_name.clone(),
+                    kind,
+                };
+                code_lenses.push(CodeLens {
+                    range: definition.name_range,
+                    command: None,
+                    data: serde_json::to_value(data).ok(),
+                });
+            };
+            push_code_lens(CodeLensKind::References);
+            if implementable {
+                push_code_lens(CodeLensKind::Implementations);
+            }
+        }
+
+        Ok(Some(code_lenses))
+    }
+
+    pub fn handle_req_code_lens_resolve(&self, code_lens: CodeLens) -> anyhow::Result<CodeLens> {
+        let Some(data) = code_lens
+            .data
+            .clone()
+            .and_then(|data| serde_json::from_value::<CodeLensData>(data).ok())
+        else {
+            return Ok(code_lens);
+        };
+
+        let mut locations = vec![];
+        let mut seen_locations = FxHashSet::default();
+        let mut seen_qualified_names = FxHashSet::default();
+        for project_container in self.project_containers.iter() {
+            let symbol_index = &project_container.symbol_index;
+            let container_locations = match data.kind {
+                CodeLensKind::References => reference_locations(symbol_index, &data.qualified_name),
+                CodeLensKind::Implementations => {
+                    let mut implementations = vec![];
+                    collect_implementations(
+                        symbol_index,
+                        &data.qualified_name,
+                        &mut seen_qualified_names,
+                        &mut implementations,
+                    );
+                    implementations
+                }
+            };
+            for location in container_locations {
+                if seen_locations.insert(location.clone()) {
+                    locations.push(location);
+                }
+            }
+        }
+
+        let title = match (data.kind, locations.len()) {
+            (CodeLensKind::References, 1) => "1 reference".to_owned(),
+            (CodeLensKind::References, count) => format!("{count} references"),
+            (CodeLensKind::Implementations, 1) => "1 implementation".to_owned(),
+            (CodeLensKind::Implementations, count) => format!("{count} implementations"),
+        };
+        let command = Command {
+            title,
+            command: SHOW_LOCATIONS_COMMAND.to_owned(),
+            arguments: Some(vec![
+                serde_json::to_value(&data.uri)?,
+                serde_json::to_value(code_lens.range.start)?,
+                serde_json::to_value(locations)?,
+            ]),
+        };
+
+        Ok(CodeLens {
+            command: Some(command),
+            ..code_lens
+        })
+    }
+}
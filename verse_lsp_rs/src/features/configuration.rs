@@ -0,0 +1,377 @@
+use std::{path::PathBuf, rc::Rc};
+
+use lsp_types::{notification::Notification, *};
+
+use crate::{
+    server::{LanguageServer, PendingRequest, VerseLspCESettings},
+    uefn,
+    verse::{CSourcePackage, SourcePackage},
+    vproject::{PackageSettings, VProjectFile, VProjectPackage},
+};
+
+/// Section of the client configuration holding [`VerseLspCESettings`].
+const CONFIGURATION_SECTION: &str = "verseCE";
+
+impl LanguageServer {
+    /// Lets clients that don't push configuration changes by themselves know we want them.
+    pub fn register_configuration(&mut self) {
+        let dynamic_registration = self
+            .client_capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_configuration.as_ref())
+            .and_then(|did_change| did_change.dynamic_registration)
+            .unwrap_or(false);
+        if !dynamic_registration {
+            return;
+        }
+
+        self.send_request::<request::RegisterCapability>(RegistrationParams {
+            registrations: vec![Registration {
+                id: notification::DidChangeConfiguration::METHOD.to_owned(),
+                method: notification::DidChangeConfiguration::METHOD.to_owned(),
+                register_options: None,
+            }],
+        });
+    }
+
+    pub fn handle_did_change_configuration(
+        &mut self,
+        params: DidChangeConfigurationParams,
+    ) -> anyhow::Result<()> {
+        // pushed settings can't be scoped to workspace folders, prefer pulling them
+        if self.supports_configuration_pull() {
+            self.pull_configuration();
+            return Ok(());
+        }
+
+        let settings = match params.settings.get(CONFIGURATION_SECTION) {
+            Some(section) => section.clone(),
+            None => params.settings,
+        };
+        if settings.is_null() {
+            return Ok(());
+        }
+        self.settings = serde_json::from_value(settings)?;
+
+        self.apply_settings();
+        Ok(())
+    }
+
+    /// Requests global settings along with settings of each workspace folder.
+    pub fn pull_configuration(&mut self) {
+        if !self.supports_configuration_pull() {
+            return;
+        }
+
+        let mut scope_uris = vec![None];
+        scope_uris.extend(
+            self.workspace_folders
+                .iter()
+                .map(|workspace_folder| Some(workspace_folder.uri.clone())),
+        );
+        let items = scope_uris
+            .iter()
+            .map(|scope_uri| ConfigurationItem {
+                scope_uri: scope_uri.clone(),
+                section: Some(CONFIGURATION_SECTION.to_owned()),
+            })
+            .collect();
+
+        let id =
+            self.send_request::<request::WorkspaceConfiguration>(ConfigurationParams { items });
+        self.pending_requests
+            .insert(id, PendingRequest::Configuration(scope_uris));
+    }
+
+    pub fn handle_configuration_response(
+        &mut self,
+        scope_uris: Vec<Option<Url>>,
+        result: serde_json::Value,
+    ) -> anyhow::Result<()> {
+        let values: Vec<serde_json::Value> = serde_json::from_value(result)?;
+        for (scope_uri, value) in scope_uris.into_iter().zip(values) {
+            if value.is_null() {
+                continue;
+            }
+            let settings: VerseLspCESettings = match serde_json::from_value(value) {
+                Ok(settings) => settings,
+                Err(err) => {
+                    log::error!("Invalid settings for scope {scope_uri:?}: {err}");
+                    continue;
+                }
+            };
+            match scope_uri {
+                Some(workspace_folder_uri) => {
+                    self.folder_settings.insert(workspace_folder_uri, settings);
+                }
+                None => self.settings = settings,
+            }
+        }
+
+        self.apply_settings();
+        Ok(())
+    }
+
+    /// Picks the Fortnite version packages default to, from digests referenced by .vproject files
+    /// or local installations. Warns when the version had to be guessed and no setting overrides it.
+    pub fn detect_fortnite_version(&mut self, workspace_folders: &[WorkspaceFolder]) {
+        let digest_dirs: Vec<PathBuf> = workspace_folders
+            .iter()
+            .flat_map(|workspace_folder| self.find_vproject_files(workspace_folder))
+            .filter_map(|vproject_path| {
+                let vproject_file = VProjectFile::read(&vproject_path).ok()?;
+                Some((vproject_path, vproject_file))
+            })
+            .flat_map(|(vproject_path, vproject_file)| {
+                vproject_file
+                    .packages
+                    .into_iter()
+                    .filter(|package| package.read_only)
+                    .filter_map(move |package| package.resolve_dir_path(&vproject_path).ok())
+            })
+            .collect();
+
+        match uefn::detect_fortnite_version(&digest_dirs) {
+            Some(detected) => {
+                log::info!(
+                    "Detected Fortnite version {} from {:?}",
+                    detected.fortnite_version,
+                    detected.source
+                );
+                self.default_fortnite_version = detected.fortnite_version;
+            }
+            None => {
+                self.default_fortnite_version = uefn::FALLBACK_FORTNITE_VERSION;
+                if self.settings.fortnite_version.is_none() {
+                    self.send_notification::<notification::ShowMessage>(ShowMessageParams {
+                        typ: MessageType::WARNING,
+                        message: format!(
+                            "VerseLspCE couldn't find a local UEFN installation, packages are compiled for Fortnite version {}. Set `fortnite_version` to override it.",
+                            uefn::FALLBACK_FORTNITE_VERSION
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    /// Settings of a workspace folder, falling back to global settings.
+    pub fn folder_settings(&self, workspace_folder: &WorkspaceFolder) -> &VerseLspCESettings {
+        self.folder_settings
+            .get(&workspace_folder.uri)
+            .unwrap_or(&self.settings)
+    }
+
+    /// Settings of a project, from lowest to highest precedence:
+    /// client settings, the workspace config file, then overrides in `projects` for this project.
+    pub fn project_settings(
+        &self,
+        workspace_folder: &WorkspaceFolder,
+        project_name: &str,
+    ) -> VerseLspCESettings {
+        self.resolve_settings(workspace_folder, Some(project_name))
+    }
+
+    /// Settings of a workspace folder regardless of projects, e.g to discover .vproject files.
+    pub fn workspace_settings(&self, workspace_folder: &WorkspaceFolder) -> VerseLspCESettings {
+        self.resolve_settings(workspace_folder, None)
+    }
+
+    fn resolve_settings(
+        &self,
+        workspace_folder: &WorkspaceFolder,
+        project_name: Option<&str>,
+    ) -> VerseLspCESettings {
+        let client_settings = self.folder_settings(workspace_folder);
+
+        let mut value = serde_json::to_value(client_settings).unwrap_or_default();
+        if let Some(workspace_config) = self.workspace_configs.get(&workspace_folder.uri) {
+            merge_json(&mut value, workspace_config.clone());
+        }
+        if let Some(project_name) = project_name
+            && let Some(project_overrides) = value
+                .get("projects")
+                .and_then(|projects| projects.get(project_name))
+                .cloned()
+        {
+            merge_json(&mut value, project_overrides);
+        }
+
+        match serde_json::from_value(value) {
+            Ok(settings) => settings,
+            Err(err) => {
+                log::error!(
+                    "Invalid settings for workspace folder {}: {err}",
+                    workspace_folder.name
+                );
+                client_settings.clone()
+            }
+        }
+    }
+
+    /// Settings a package of a .vproject file gets registered with.
+    pub fn package_settings(
+        &self,
+        settings: &VerseLspCESettings,
+        vproject_package: &VProjectPackage,
+    ) -> PackageSettings {
+        let mut package_settings = vproject_package.desc.settings.clone();
+        if package_settings.fortnite_version.is_none() {
+            package_settings.fortnite_version = settings
+                .fortnite_version
+                .or(Some(self.default_fortnite_version));
+        }
+        package_settings
+    }
+
+    /// Resolves settings of each project again. Projects are built again if their packages are affected.
+    pub fn apply_settings(&mut self) {
+        let mut reloaded_indices = vec![];
+        for index in 0..self.project_containers.len() {
+            let project_container = &self.project_containers[index];
+            let settings = self.project_settings(
+                &project_container.workspace_folder,
+                &project_container.project_name(),
+            );
+            // standalone projects don't depend on .vproject packages nor discovered files
+            if !project_container.standalone
+                && (settings.excluded_packages != project_container.settings.excluded_packages
+                    || settings.files != project_container.settings.files)
+            {
+                reloaded_indices.push(index);
+                continue;
+            }
+
+            let updated_packages: Vec<(usize, PackageSettings)> = project_container
+                .packages
+                .iter()
+                .enumerate()
+                .filter_map(|(package_index, package)| {
+                    let vproject_package = project_container
+                        .vproject_file
+                        .packages
+                        .iter()
+                        .find(|vproject_package| vproject_package.desc.name == package.name)?;
+                    let package_settings = self.package_settings(&settings, vproject_package);
+                    (package_settings.fortnite_version != package.settings.fortnite_version)
+                        .then_some((package_index, package_settings))
+                })
+                .collect();
+
+            let project_container = &mut self.project_containers[index];
+            project_container.settings = settings;
+            if updated_packages.is_empty() {
+                continue;
+            }
+            for (package_index, package_settings) in updated_packages {
+                let package = &project_container.packages[package_index];
+                crate::update_package_settings(&package.c_package, &package_settings);
+                project_container.packages[package_index] = Rc::new(SourcePackage {
+                    name: package.name.clone(),
+                    verse_path: package.verse_path.clone(),
+                    dir_path: package.dir_path.clone(),
+                    read_only: package.read_only,
+                    role: package.role,
+                    settings: package_settings,
+                    c_package: CSourcePackage(package.c_package.0),
+                });
+            }
+            project_container.needs_build = true;
+        }
+
+        // packages can't be unregistered, projects with different packages or files are registered again
+        for index in reloaded_indices.into_iter().rev() {
+            self.reload_project_container(index);
+        }
+        self.discover_project_containers();
+
+        self.publish_diagnostics();
+        self.refresh_inlay_hints();
+    }
+
+    pub fn reload_project_container(&mut self, index: usize) {
+        let removed = self.project_containers.remove(index);
+        crate::unregister_project_container(removed.c_container);
+        let Ok(vproject_path) = removed.vproject_uri.to_file_path() else {
+            return;
+        };
+        self.register_project_container(vproject_path, removed.workspace_folder);
+
+        // clear diagnostics of files the new project container doesn't report anymore
+        if let Some(project_container) = self.project_containers.last_mut() {
+            project_container.stale_diagnostic_uris.extend(
+                removed
+                    .diagnostics
+                    .into_keys()
+                    .filter(|uri| !project_container.diagnostics.contains_key(uri)),
+            );
+        }
+    }
+
+    fn supports_configuration_pull(&self) -> bool {
+        self.client_capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.configuration)
+            .unwrap_or(false)
+    }
+}
+
+/// Deep merges `overlay` into `base`, objects are merged key by key.
+fn merge_json(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(base_value) => merge_json(base_value, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn merge_nested_objects() {
+        let mut base = json!({
+            "fortnite_version": 3700,
+            "files": { "exclude": ["a"], "max_depth": 4 },
+        });
+        merge_json(
+            &mut base,
+            json!({
+                "files": { "max_depth": 8 },
+                "excluded_packages": ["B"],
+            }),
+        );
+        assert_eq!(
+            base,
+            json!({
+                "fortnite_version": 3700,
+                "files": { "exclude": ["a"], "max_depth": 8 },
+                "excluded_packages": ["B"],
+            })
+        );
+    }
+
+    #[test]
+    fn merge_replaces_other_values() {
+        let mut base = json!({ "exclude": ["a", "b"], "files": { "max_depth": 4 } });
+        merge_json(&mut base, json!({ "exclude": ["c"], "files": null }));
+        assert_eq!(base, json!({ "exclude": ["c"], "files": null }));
+
+        let mut base = json!("a");
+        merge_json(&mut base, json!({ "b": 1 }));
+        assert_eq!(base, json!({ "b": 1 }));
+    }
+}
@@ -0,0 +1,126 @@
+use lsp_types::*;
+
+use crate::{
+    server::LanguageServer,
+    symbol_index::{DefinitionKind, SymbolDefinition, SymbolReference},
+};
+
+impl LanguageServer {
+    pub fn handle_req_document_highlight(
+        &self,
+        params: DocumentHighlightParams,
+    ) -> anyhow::Result<Option<Vec<DocumentHighlight>>> {
+        let params = params.text_document_position_params;
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let symbols = self.resolve_symbol_at(&uri, params.position);
+        // occurrences within a single file are the same for every project container
+        let Some(symbol) = symbols.first() else {
+            return Ok(None);
+        };
+        let Some(file_symbols) = symbol.project_container.symbol_index.file_symbols(&uri) else {
+            return Ok(None);
+        };
+
+        let mut highlights: Vec<DocumentHighlight> = file_symbols
+            .definitions
+            .iter()
+            .filter(|definition| definition.qualified_name == symbol.qualified_name)
+            .map(|definition| DocumentHighlight {
+                range: definition.name_range,
+                kind: Some(definition_highlight_kind(definition)),
+            })
+            .collect();
+
+        for reference in file_symbols.references.iter() {
+            if reference.qualified_name != symbol.qualified_name
+                || highlights
+                    .iter()
+                    .any(|highlight| highlight.range == reference.range)
+            {
+                continue;
+            }
+            highlights.push(DocumentHighlight {
+                range: reference.range,
+                kind: Some(reference_highlight_kind(reference)),
+            });
+        }
+
+        Ok(Some(highlights))
+    }
+}
+
+/// Only data defined with a value is written, other declarations are plain text.
+fn definition_highlight_kind(definition: &SymbolDefinition) -> DocumentHighlightKind {
+    if definition.kind == DefinitionKind::Data && definition.initialized {
+        DocumentHighlightKind::WRITE
+    } else {
+        DocumentHighlightKind::TEXT
+    }
+}
+
+fn reference_highlight_kind(reference: &SymbolReference) -> DocumentHighlightKind {
+    if reference.write {
+        DocumentHighlightKind::WRITE
+    } else {
+        DocumentHighlightKind::READ
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn definition(kind: DefinitionKind, initialized: bool) -> SymbolDefinition {
+        SymbolDefinition {
+            qualified_name: "/M.X".to_owned(),
+            name: "X".to_owned(),
+            container_name: "/M".to_owned(),
+            kind,
+            range: Range::default(),
+            name_range: Range::default(),
+            supertypes: vec![],
+            overrides: None,
+            type_name: None,
+            initialized,
+        }
+    }
+
+    fn reference(write: bool) -> SymbolReference {
+        SymbolReference {
+            qualified_name: "/M.X".to_owned(),
+            range: Range::default(),
+            write,
+            call: false,
+            caller: None,
+        }
+    }
+
+    #[test]
+    fn definition_kinds() {
+        assert_eq!(
+            definition_highlight_kind(&definition(DefinitionKind::Data, true)),
+            DocumentHighlightKind::WRITE
+        );
+        assert_eq!(
+            definition_highlight_kind(&definition(DefinitionKind::Data, false)),
+            DocumentHighlightKind::TEXT
+        );
+        assert_eq!(
+            definition_highlight_kind(&definition(DefinitionKind::Function, false)),
+            DocumentHighlightKind::TEXT
+        );
+    }
+
+    #[test]
+    fn reference_kinds() {
+        assert_eq!(
+            reference_highlight_kind(&reference(true)),
+            DocumentHighlightKind::WRITE
+        );
+        assert_eq!(
+            reference_highlight_kind(&reference(false)),
+            DocumentHighlightKind::READ
+        );
+    }
+}
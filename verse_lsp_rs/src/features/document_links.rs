@@ -0,0 +1,157 @@
+use std::{fs, path::Path};
+
+use lsp_types::*;
+
+use crate::{server::LanguageServer, symbol_index::DefinitionKind, verse::ProjectContainer};
+
+#[derive(Debug)]
+pub struct UsingPathsAccumulator {
+    pub paths: Vec<(String, Range)>,
+}
+
+pub fn capabilities_document_link() -> DocumentLinkOptions {
+    DocumentLinkOptions {
+        resolve_provider: Some(false),
+        work_done_progress_options: Default::default(),
+    }
+}
+
+impl LanguageServer {
+    pub fn handle_req_document_link(
+        &self,
+        params: DocumentLinkParams,
+    ) -> anyhow::Result<Option<Vec<DocumentLink>>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let path = self.uri_to_file_path(&params.text_document.uri)?;
+        let path_str = path.to_string_lossy();
+
+        let Some(project_container) = self.find_project_container(&uri) else {
+            return Ok(None);
+        };
+
+        let mut acc = UsingPathsAccumulator { paths: vec![] };
+        crate::get_using_paths(&project_container.c_container, &path_str, &mut acc);
+
+        let file_module_path = file_module_path(project_container, &path);
+        let links = acc
+            .paths
+            .into_iter()
+            .filter_map(|(module_path, range)| {
+                let target = if module_path.starts_with('/') {
+                    resolve_module_path(project_container, &module_path)
+                } else {
+                    // relative paths, e.g `using { Sibling }`, are looked up
+                    // from the file's module outwards
+                    let file_module_path = file_module_path.as_deref()?;
+                    candidate_module_paths(file_module_path, &module_path)
+                        .iter()
+                        .find_map(|candidate| resolve_module_path(project_container, candidate))
+                }?;
+                Some(DocumentLink {
+                    range,
+                    target: Some(target),
+                    tooltip: Some(module_path),
+                    data: None,
+                })
+            })
+            .collect();
+
+        Ok(Some(links))
+    }
+}
+
+/// Module of a source file, named after its directory within the package.
+fn file_module_path(project_container: &ProjectContainer, path: &Path) -> Option<String> {
+    let dir_path = path.parent()?;
+    project_container.packages.iter().find_map(|package| {
+        let relative_dir = dir_path.strip_prefix(&package.dir_path).ok()?;
+        Some(relative_dir.components().fold(
+            package.verse_path.clone(),
+            |module_path, component| {
+                format!("{module_path}/{}", component.as_os_str().to_string_lossy())
+            },
+        ))
+    })
+}
+
+/// Absolute paths a relative module path may refer to, innermost first.
+fn candidate_module_paths(file_module_path: &str, relative_path: &str) -> Vec<String> {
+    let mut candidates = vec![];
+    let mut scope = file_module_path;
+    loop {
+        candidates.push(format!("{scope}/{relative_path}"));
+        match scope.rsplit_once('/') {
+            Some((parent, _)) if !parent.is_empty() => scope = parent,
+            _ => break,
+        }
+    }
+    candidates
+}
+
+/// Resolves a module path to a file of its directory within a source package,
+/// or to the file defining it otherwise, e.g modules of digests.
+fn resolve_module_path(project_container: &ProjectContainer, module_path: &str) -> Option<Url> {
+    for package in project_container.packages.iter() {
+        let Some(relative_path) = module_path.strip_prefix(&package.verse_path) else {
+            continue;
+        };
+        if !relative_path.is_empty() && !relative_path.starts_with('/') {
+            continue;
+        }
+        let dir_path = relative_path
+            .split('/')
+            .filter(|segment| !segment.is_empty())
+            .fold(package.dir_path.clone(), |dir_path, segment| {
+                dir_path.join(segment)
+            });
+        // directories can't be opened, link to one of the module's files
+        if let Some(file_path) = first_verse_file(&dir_path) {
+            return Url::from_file_path(file_path).ok();
+        }
+    }
+
+    let (scope, name) = module_path.rsplit_once('/')?;
+    let (uri, definition) =
+        project_container
+            .symbol_index
+            .definitions()
+            .find(|(_, definition)| {
+                definition.kind == DefinitionKind::Module
+                    && definition.container_name == scope
+                    && definition.name == name
+            })?;
+    let mut target = uri.clone();
+    target.set_fragment(Some(&format!("L{}", definition.name_range.start.line + 1)));
+    Some(target)
+}
+
+fn first_verse_file(dir_path: &Path) -> Option<std::path::PathBuf> {
+    fs::read_dir(dir_path)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "verse"))
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_module_candidates() {
+        assert_eq!(
+            candidate_module_paths("/Game@fortnite.com/Main/Devices", "Util"),
+            [
+                "/Game@fortnite.com/Main/Devices/Util",
+                "/Game@fortnite.com/Main/Util",
+                "/Game@fortnite.com/Util",
+            ]
+        );
+        assert_eq!(
+            candidate_module_paths("/Game@fortnite.com", "Util"),
+            ["/Game@fortnite.com/Util"]
+        );
+    }
+}
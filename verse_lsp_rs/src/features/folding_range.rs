@@ -0,0 +1,106 @@
+use fxhash::FxHashMap;
+use lsp_types::*;
+
+use crate::{ffi, server::LanguageServer};
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FoldingRangeEntryKind {
+    Region,
+    Comment,
+    Imports,
+}
+
+#[repr(C)]
+#[derive(Debug)]
+pub struct FoldingRangeEntry {
+    pub kind: FoldingRangeEntryKind,
+    pub span: ffi::SSourceSpan,
+}
+
+#[derive(Debug)]
+pub struct FoldingRangesAccumulator {
+    pub entries: Vec<FoldingRangeEntry>,
+}
+
+impl LanguageServer {
+    pub fn handle_req_folding_range(
+        &self,
+        params: FoldingRangeParams,
+    ) -> anyhow::Result<Option<Vec<FoldingRange>>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let path = self.uri_to_file_path(&params.text_document.uri)?;
+        let path_str = path.to_string_lossy();
+
+        let Some(project_container) = self.find_project_container(&uri) else {
+            return Ok(None);
+        };
+
+        let mut acc = FoldingRangesAccumulator { entries: vec![] };
+        crate::get_folding_ranges(&project_container.c_container, &path_str, &mut acc);
+
+        acc.entries
+            .sort_unstable_by_key(|entry| (entry.span.begin_row, entry.span.begin_col));
+
+        // keep the largest range starting on each line, clients can only fold one of them
+        let mut ranges_by_start_line: FxHashMap<u32, FoldingRange> = FxHashMap::default();
+        let mut add_range = |kind: FoldingRangeEntryKind, start_line, end_line| {
+            if end_line <= start_line {
+                return;
+            }
+            let range = ranges_by_start_line
+                .entry(start_line)
+                .or_insert_with(|| FoldingRange {
+                    start_line,
+                    end_line,
+                    kind: Some(kind.to_lsp_kind()),
+                    ..Default::default()
+                });
+            if end_line > range.end_line {
+                range.end_line = end_line;
+                range.kind = Some(kind.to_lsp_kind());
+            }
+        };
+
+        // consecutive line comments and using lines are folded as one group
+        let mut group: Option<(FoldingRangeEntryKind, u32, u32)> = None;
+        for entry in acc.entries {
+            let (start_line, end_line) = (entry.span.begin_row, entry.span.end_row);
+            match entry.kind {
+                FoldingRangeEntryKind::Region => {
+                    add_range(entry.kind, start_line, end_line);
+                }
+                FoldingRangeEntryKind::Comment | FoldingRangeEntryKind::Imports => {
+                    if let Some((group_kind, _, group_end_line)) = group.as_mut()
+                        && *group_kind == entry.kind
+                        && start_line <= *group_end_line + 1
+                    {
+                        *group_end_line = end_line.max(*group_end_line);
+                        continue;
+                    }
+                    if let Some((group_kind, group_start_line, group_end_line)) =
+                        group.replace((entry.kind, start_line, end_line))
+                    {
+                        add_range(group_kind, group_start_line, group_end_line);
+                    }
+                }
+            }
+        }
+        if let Some((group_kind, group_start_line, group_end_line)) = group {
+            add_range(group_kind, group_start_line, group_end_line);
+        }
+
+        Ok(Some(ranges_by_start_line.into_values().collect()))
+    }
+}
+
+impl FoldingRangeEntryKind {
+    fn to_lsp_kind(self) -> FoldingRangeKind {
+        match self {
+            Self::Region => FoldingRangeKind::Region,
+            Self::Comment => FoldingRangeKind::Comment,
+            Self::Imports => FoldingRangeKind::Imports,
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use anyhow::bail;
+use lsp_types::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{ffi, server::LanguageServer};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FormattingSettings {
+    /// Spaces per indentation level, defaults to the editor's tab size.
+    pub indent_width: Option<u32>,
+    /// Indent with tabs or spaces, defaults to the editor's preference.
+    pub indent_style: Option<IndentStyle>,
+    /// Braced blocks on longer lines are split into one statement per line.
+    pub line_length: u32,
+}
+
+impl Default for FormattingSettings {
+    fn default() -> Self {
+        Self {
+            indent_width: None,
+            indent_style: None,
+            line_length: 120,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndentStyle {
+    Tab,
+    Space,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FormattingResult {
+    Formatted,
+    NoSyntaxTree,
+    SyntaxErrors,
+    /// The syntax tree doesn't match the source text anymore.
+    OutOfDate,
+}
+
+#[derive(Debug)]
+pub struct TextEditsAccumulator {
+    pub edits: Vec<TextEdit>,
+}
+
+impl LanguageServer {
+    pub fn handle_req_formatting(
+        &self,
+        params: DocumentFormattingParams,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        self.format(&params.text_document.uri, &params.options, None)
+    }
+
+    pub fn handle_req_range_formatting(
+        &self,
+        params: DocumentRangeFormattingParams,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        self.format(
+            &params.text_document.uri,
+            &params.options,
+            Some(params.range),
+        )
+    }
+
+    fn format(
+        &self,
+        uri: &Url,
+        options: &FormattingOptions,
+        range: Option<Range>,
+    ) -> anyhow::Result<Option<Vec<TextEdit>>> {
+        let path = self.uri_to_file_path(uri)?;
+        let path_str = path.to_string_lossy();
+        let uri = self.normalize_uri(uri)?;
+
+        let Some(project_container) = self.find_project_container(&uri) else {
+            return Ok(None);
+        };
+
+        let (begin_row, end_row) = range.map_or((0, 0), range_rows);
+        let settings = &project_container.settings.formatting;
+        let options = ffi::SFormattingOptions {
+            indent_width: settings.indent_width.unwrap_or(options.tab_size),
+            indent_with_tabs: settings
+                .indent_style
+                .map_or(!options.insert_spaces, |style| style == IndentStyle::Tab),
+            line_length: settings.line_length,
+            has_range: range.is_some(),
+            begin_row,
+            end_row,
+        };
+
+        let mut acc = TextEditsAccumulator { edits: vec![] };
+        match crate::format(&project_container.c_container, &path_str, options, &mut acc) {
+            FormattingResult::Formatted => Ok(Some(acc.edits)),
+            FormattingResult::NoSyntaxTree => Ok(None),
+            FormattingResult::SyntaxErrors => bail!("Cannot format a file with syntax errors"),
+            // formatting requests are compile gated so the file was parsed again beforehand,
+            // unless the build stopped before parsing it
+            FormattingResult::OutOfDate => {
+                bail!("Cannot format {uri}, the project snapshot is out of date")
+            }
+        }
+    }
+}
+
+/// Rows of the statements to format, a range ending at the start of a line
+/// (as when selecting whole lines) excludes that line.
+fn range_rows(range: Range) -> (u32, u32) {
+    let end_row = if range.end.character == 0 && range.end.line > range.start.line {
+        range.end.line - 1
+    } else {
+        range.end.line
+    };
+    (range.start.line, end_row)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: (u32, u32), end: (u32, u32)) -> Range {
+        Range::new(Position::new(start.0, start.1), Position::new(end.0, end.1))
+    }
+
+    #[test]
+    fn rows_of_range() {
+        assert_eq!(range_rows(range((2, 4), (5, 3))), (2, 5));
+        assert_eq!(range_rows(range((2, 0), (5, 0))), (2, 4));
+        assert_eq!(range_rows(range((2, 0), (3, 0))), (2, 2));
+        // an empty range at the start of a line still formats that line
+        assert_eq!(range_rows(range((2, 0), (2, 0))), (2, 2));
+    }
+}
@@ -0,0 +1,63 @@
+use fxhash::FxHashSet;
+use lsp_types::{
+    request::{GotoImplementationParams, GotoImplementationResponse},
+    *,
+};
+
+use crate::{
+    server::LanguageServer,
+    symbol_index::{DefinitionKind, SymbolIndex},
+};
+
+impl LanguageServer {
+    pub fn handle_req_implementation(
+        &self,
+        params: GotoImplementationParams,
+    ) -> anyhow::Result<Option<GotoImplementationResponse>> {
+        let uri = self.normalize_uri(&params.text_document_position_params.text_document.uri)?;
+        let position = params.text_document_position_params.position;
+
+        let mut locations = vec![];
+        let mut seen_qualified_names = FxHashSet::default();
+        for symbol in self.resolve_symbol_at(&uri, position) {
+            collect_implementations(
+                &symbol.project_container.symbol_index,
+                &symbol.qualified_name,
+                &mut seen_qualified_names,
+                &mut locations,
+            );
+        }
+
+        Ok(Some(GotoDefinitionResponse::Array(locations)))
+    }
+}
+
+/// Collects overrides of functions and subtypes of classes and interfaces, transitively.
+pub fn collect_implementations(
+    symbol_index: &SymbolIndex,
+    qualified_name: &str,
+    seen_qualified_names: &mut FxHashSet<String>,
+    locations: &mut Vec<Location>,
+) {
+    // implementations of implementations are implementations too
+    let mut pending = vec![qualified_name.to_owned()];
+    while let Some(qualified_name) = pending.pop() {
+        let Some((_, definition)) = symbol_index.find_definitions(&qualified_name).next() else {
+            continue;
+        };
+        let implementations: Vec<_> = match definition.kind {
+            DefinitionKind::Function => symbol_index.find_overrides(&qualified_name).collect(),
+            DefinitionKind::Class | DefinitionKind::Interface => {
+                symbol_index.find_subtypes(&qualified_name).collect()
+            }
+            _ => vec![],
+        };
+
+        for (uri, implementation) in implementations {
+            if seen_qualified_names.insert(implementation.qualified_name.clone()) {
+                locations.push(Location::new(uri.clone(), implementation.name_range));
+                pending.push(implementation.qualified_name.clone());
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use lsp_types::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{server::LanguageServer, utils};
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct InlayHintsSettings {
+    /// Inferred types of `X := ...` bindings.
+    pub binding_types: bool,
+    /// Inferred types of `for` iteration variables.
+    pub iteration_types: bool,
+    /// Parameter names at call sites.
+    pub parameter_names: bool,
+    /// Effects of functions without explicit effect specifiers.
+    pub effects: bool,
+}
+
+impl Default for InlayHintsSettings {
+    fn default() -> Self {
+        Self {
+            binding_types: true,
+            iteration_types: true,
+            parameter_names: true,
+            effects: false,
+        }
+    }
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum InlayHintEntryKind {
+    BindingType,
+    IterationType,
+    ParameterName,
+    Effects,
+}
+
+#[derive(Debug)]
+pub struct InlayHintEntry {
+    pub kind: InlayHintEntryKind,
+    pub label: String,
+    pub position: Position,
+}
+
+#[derive(Debug)]
+pub struct InlayHintsAccumulator {
+    pub hints: Vec<InlayHintEntry>,
+}
+
+impl InlayHintsSettings {
+    fn is_enabled(&self, kind: InlayHintEntryKind) -> bool {
+        match kind {
+            InlayHintEntryKind::BindingType => self.binding_types,
+            InlayHintEntryKind::IterationType => self.iteration_types,
+            InlayHintEntryKind::ParameterName => self.parameter_names,
+            InlayHintEntryKind::Effects => self.effects,
+        }
+    }
+}
+
+impl LanguageServer {
+    pub fn handle_req_inlay_hint(
+        &self,
+        params: InlayHintParams,
+    ) -> anyhow::Result<Option<Vec<InlayHint>>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let path = self.uri_to_file_path(&params.text_document.uri)?;
+        let path_str = path.to_string_lossy();
+
+        let Some(project_container) = self.find_project_container(&uri) else {
+            return Ok(None);
+        };
+
+        let mut acc = InlayHintsAccumulator { hints: vec![] };
+        crate::get_inlay_hints(&project_container.c_container, &path_str, &mut acc);
+
+        let settings = &project_container.settings.inlay_hints;
+        let inlay_hints = acc
+            .hints
+            .into_iter()
+            .filter(|hint| {
+                settings.is_enabled(hint.kind)
+                    && utils::range_contains(&params.range, hint.position)
+            })
+            .map(|hint| InlayHint {
+                position: hint.position,
+                label: InlayHintLabel::String(hint.label),
+                kind: Some(match hint.kind {
+                    InlayHintEntryKind::ParameterName => InlayHintKind::PARAMETER,
+                    _ => InlayHintKind::TYPE,
+                }),
+                text_edits: None,
+                tooltip: None,
+                padding_left: None,
+                padding_right: Some(hint.kind == InlayHintEntryKind::ParameterName),
+                data: None,
+            })
+            .collect();
+
+        Ok(Some(inlay_hints))
+    }
+
+    /// Asks the client to request inlay hints again, as they may be outdated after a build.
+    pub fn refresh_inlay_hints(&mut self) {
+        let refresh_support = self
+            .client_capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.inlay_hint.as_ref())
+            .and_then(|inlay_hint| inlay_hint.refresh_support)
+            .unwrap_or(false);
+        if refresh_support {
+            self.send_request::<request::InlayHintRefreshRequest>(());
+        }
+    }
+}
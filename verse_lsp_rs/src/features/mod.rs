@@ -1,2 +1,23 @@
+pub mod call_hierarchy;
+pub mod code_actions;
+pub mod code_lens;
+pub mod configuration;
+pub mod document_highlight;
+pub mod document_links;
+pub mod folding_range;
+pub mod formatting;
+pub mod implementation;
+pub mod inlay_hints;
+pub mod project_info;
+pub mod references;
+pub mod rename;
+pub mod selection_range;
 pub mod semantic_tokens;
+pub mod status;
+pub mod syntax_tree;
+pub mod type_definition;
+pub mod type_hierarchy;
+pub mod vproject_file;
 pub mod workspace;
+pub mod workspace_config;
+pub mod workspace_symbols;
@@ -0,0 +1,151 @@
+use std::{path::PathBuf, time::UNIX_EPOCH};
+
+use lsp_types::{request::Request, *};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    server::LanguageServer,
+    verse::{ProjectContainer, SourcePackage},
+    vproject::{PackageRole, VerseScope},
+};
+
+/// Custom request describing what the server loaded from each .vproject file.
+#[derive(Debug)]
+pub enum ProjectInfoRequest {}
+
+impl Request for ProjectInfoRequest {
+    type Params = Option<ProjectInfoParams>;
+    type Result = Vec<ProjectInfo>;
+    const METHOD: &'static str = "verseCE/projectInfo";
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectInfoParams {
+    /// Only describe the project of this .vproject file.
+    #[serde(default)]
+    pub vproject_uri: Option<Url>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectInfo {
+    pub workspace_folder: Url,
+    pub vproject_uri: Url,
+    pub packages: Vec<PackageInfo>,
+    pub file_count: usize,
+    /// Milliseconds since the Unix epoch.
+    pub last_build_time: Option<u64>,
+    pub last_build_duration_ms: Option<u64>,
+    pub needs_build: bool,
+    pub diagnostics: DiagnosticCounts,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageInfo {
+    pub name: String,
+    pub verse_path: String,
+    pub dir_path: PathBuf,
+    pub read_only: bool,
+    pub role: PackageRole,
+    pub scope: VerseScope,
+    pub verse_version: Option<u32>,
+    pub fortnite_version: Option<u32>,
+    pub dependency_packages: Vec<String>,
+    pub file_count: usize,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticCounts {
+    pub errors: usize,
+    pub warnings: usize,
+    pub information: usize,
+    pub hints: usize,
+}
+
+impl LanguageServer {
+    pub fn handle_req_project_info(
+        &self,
+        params: Option<ProjectInfoParams>,
+    ) -> anyhow::Result<Vec<ProjectInfo>> {
+        let vproject_uri = params.and_then(|params| params.vproject_uri);
+
+        Ok(self
+            .project_containers
+            .iter()
+            .filter(|project_container| {
+                vproject_uri
+                    .as_ref()
+                    .is_none_or(|uri| *uri == project_container.vproject_uri)
+            })
+            .map(project_info)
+            .collect())
+    }
+}
+
+fn project_info(project_container: &ProjectContainer) -> ProjectInfo {
+    let packages = project_container
+        .packages
+        .iter()
+        .map(|package| package_info(project_container, package))
+        .collect();
+
+    let (last_build_time, last_build_duration_ms) = match project_container.last_build {
+        Some((time, duration)) => (
+            time.duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|since_epoch| since_epoch.as_millis() as u64),
+            Some(duration.as_millis() as u64),
+        ),
+        None => (None, None),
+    };
+
+    ProjectInfo {
+        workspace_folder: project_container.workspace_folder.uri.clone(),
+        vproject_uri: project_container.vproject_uri.clone(),
+        packages,
+        file_count: project_container.file_cache.len(),
+        last_build_time,
+        last_build_duration_ms,
+        needs_build: project_container.needs_build,
+        diagnostics: diagnostic_counts(project_container),
+    }
+}
+
+fn package_info(project_container: &ProjectContainer, package: &SourcePackage) -> PackageInfo {
+    let settings = &package.settings;
+    PackageInfo {
+        name: package.name.clone(),
+        verse_path: package.verse_path.clone(),
+        dir_path: package.dir_path.clone(),
+        read_only: package.read_only,
+        role: package.role,
+        scope: settings.verse_scope,
+        verse_version: settings.verse_version,
+        fortnite_version: settings.fortnite_version,
+        dependency_packages: settings.dependency_packages.clone(),
+        file_count: project_container.package_file_count(package),
+    }
+}
+
+pub fn diagnostic_counts(project_container: &ProjectContainer) -> DiagnosticCounts {
+    let mut counts = DiagnosticCounts::default();
+    let diagnostics_settings = &project_container.settings.diagnostics;
+    for diagnostic in project_container
+        .diagnostics
+        .values()
+        .flatten()
+        .filter(|diagnostic| diagnostics_settings.is_reported(diagnostic))
+    {
+        match diagnostic.severity {
+            Some(DiagnosticSeverity::WARNING) => counts.warnings += 1,
+            Some(DiagnosticSeverity::INFORMATION) => counts.information += 1,
+            Some(DiagnosticSeverity::HINT) => counts.hints += 1,
+            // unspecified severity is shown as an error by clients
+            _ => counts.errors += 1,
+        }
+    }
+    counts
+}
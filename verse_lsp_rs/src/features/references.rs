@@ -0,0 +1,77 @@
+use fxhash::FxHashSet;
+use lsp_types::*;
+
+use crate::{server::LanguageServer, symbol_index::SymbolIndex, verse::ProjectContainer};
+
+/// A symbol resolved from an identifier within a project container.
+pub struct ResolvedSymbol<'a> {
+    pub project_container: &'a ProjectContainer,
+    /// Qualified name of the definition.
+    pub qualified_name: String,
+    /// Range of the identifier the symbol was resolved from.
+    pub range: Range,
+}
+
+impl LanguageServer {
+    pub fn handle_req_references(
+        &self,
+        params: ReferenceParams,
+    ) -> anyhow::Result<Option<Vec<Location>>> {
+        let uri = self.normalize_uri(&params.text_document_position.text_document.uri)?;
+        let position = params.text_document_position.position;
+
+        let mut locations = vec![];
+        let mut seen_locations = FxHashSet::default();
+        for symbol in self.resolve_symbol_at(&uri, position) {
+            let symbol_index = &symbol.project_container.symbol_index;
+
+            if params.context.include_declaration {
+                for (uri, definition) in symbol_index.find_definitions(&symbol.qualified_name) {
+                    let declaration = Location::new(uri.clone(), definition.name_range);
+                    if seen_locations.insert(declaration.clone()) {
+                        locations.push(declaration);
+                    }
+                }
+            }
+
+            for location in reference_locations(symbol_index, &symbol.qualified_name) {
+                if seen_locations.insert(location.clone()) {
+                    locations.push(location);
+                }
+            }
+        }
+
+        Ok(Some(locations))
+    }
+
+    /// Resolves the symbol at a position in every project container that contains the file.
+    pub fn resolve_symbol_at(&self, uri: &Url, position: Position) -> Vec<ResolvedSymbol<'_>> {
+        self.project_containers
+            .iter()
+            .flat_map(|project_container| {
+                let (qualified_name, range) =
+                    project_container.symbol_index.symbol_at(uri, position)?;
+                Some(ResolvedSymbol {
+                    project_container,
+                    qualified_name: qualified_name.to_owned(),
+                    range,
+                })
+            })
+            .collect()
+    }
+}
+
+/// Locations referencing a symbol, declarations excluded.
+pub fn reference_locations(symbol_index: &SymbolIndex, qualified_name: &str) -> Vec<Location> {
+    let declarations: Vec<Location> = symbol_index
+        .find_definitions(qualified_name)
+        .map(|(uri, definition)| Location::new(uri.clone(), definition.name_range))
+        .collect();
+
+    symbol_index
+        .find_references(qualified_name)
+        .map(|(uri, reference)| Location::new(uri.clone(), reference.range))
+        // definitions may resolve their own identifier
+        .filter(|location| !declarations.contains(location))
+        .collect()
+}
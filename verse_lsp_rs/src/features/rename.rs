@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+use anyhow::bail;
+use fxhash::FxHashSet;
+use lsp_types::*;
+
+use crate::{features::references::ResolvedSymbol, server::LanguageServer, vproject::PackageRole};
+
+/// Keywords of the Verse grammar, which can't be used as identifiers.
+/// Macros such as `spawn` or `race` and types such as `map` are regular identifiers.
+const RESERVED_WORDS: &[&str] = &[
+    "and",
+    "block",
+    "break",
+    "case",
+    "class",
+    "defer",
+    "else",
+    "enum",
+    "false",
+    "for",
+    "if",
+    "interface",
+    "loop",
+    "module",
+    "not",
+    "or",
+    "return",
+    "set",
+    "struct",
+    "then",
+    "true",
+    "using",
+    "var",
+];
+
+pub fn capabilities_rename() -> OneOf<bool, RenameOptions> {
+    OneOf::Right(RenameOptions {
+        prepare_provider: Some(true),
+        work_done_progress_options: WorkDoneProgressOptions {
+            work_done_progress: Some(false),
+        },
+    })
+}
+
+impl LanguageServer {
+    pub fn handle_req_prepare_rename(
+        &self,
+        params: TextDocumentPositionParams,
+    ) -> anyhow::Result<Option<PrepareRenameResponse>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let symbols = self.resolve_symbol_at(&uri, params.position);
+        let Some(symbol) = symbols.first() else {
+            return Ok(None);
+        };
+        let name = self.check_renamable(&symbols)?;
+
+        Ok(Some(PrepareRenameResponse::RangeWithPlaceholder {
+            range: symbol.range,
+            placeholder: name,
+        }))
+    }
+
+    pub fn handle_req_rename(&self, params: RenameParams) -> anyhow::Result<Option<WorkspaceEdit>> {
+        let uri = self.normalize_uri(&params.text_document_position.text_document.uri)?;
+        let new_name = params.new_name;
+
+        let symbols = self.resolve_symbol_at(&uri, params.text_document_position.position);
+        if symbols.is_empty() {
+            return Ok(None);
+        }
+        let name = self.check_renamable(&symbols)?;
+        if name == new_name {
+            return Ok(None);
+        }
+        validate_new_name(&new_name)?;
+
+        let mut changes: HashMap<Url, Vec<TextEdit>> = HashMap::new();
+        let mut seen_locations = FxHashSet::default();
+        for symbol in symbols.iter() {
+            let symbol_index = &symbol.project_container.symbol_index;
+
+            let mut locations = vec![];
+            for (uri, definition) in symbol_index.find_definitions(&symbol.qualified_name) {
+                // Verse doesn't allow shadowing, so enclosing and nested scopes collide as well
+                let collision =
+                    symbol_index
+                        .find_definitions_named(&new_name)
+                        .find(|(_, other)| {
+                            other.qualified_name != symbol.qualified_name
+                                && scopes_overlap(&definition.container_name, &other.container_name)
+                        });
+                if let Some((_, other)) = collision {
+                    bail!(
+                        "Cannot rename `{name}` to `{new_name}`: `{new_name}` is already defined in `{}`",
+                        other.container_name
+                    );
+                }
+                locations.push(Location::new(uri.clone(), definition.name_range));
+            }
+            for (uri, reference) in symbol_index.find_references(&symbol.qualified_name) {
+                locations.push(Location::new(uri.clone(), reference.range));
+            }
+
+            for location in locations {
+                if !seen_locations.insert(location.clone()) {
+                    continue;
+                }
+                changes.entry(location.uri).or_default().push(TextEdit {
+                    range: location.range,
+                    new_text: new_name.clone(),
+                });
+            }
+        }
+
+        Ok(Some(WorkspaceEdit {
+            changes: Some(changes),
+            ..Default::default()
+        }))
+    }
+
+    /// Ensures a symbol is defined in a package we're allowed to edit.
+    /// Returns the current name of the symbol.
+    fn check_renamable(&self, symbols: &[ResolvedSymbol]) -> anyhow::Result<String> {
+        let mut name = None;
+        for symbol in symbols {
+            let project_container = symbol.project_container;
+            for (uri, definition) in project_container
+                .symbol_index
+                .find_definitions(&symbol.qualified_name)
+            {
+                let Some(package) = project_container.find_package(uri) else {
+                    bail!(
+                        "Cannot rename `{}`: it is not defined in any package of the project",
+                        definition.name
+                    );
+                };
+                if package.read_only {
+                    bail!(
+                        "Cannot rename `{}`: it is defined in read-only package `{}`",
+                        definition.name,
+                        package.name
+                    );
+                }
+                if package.role == PackageRole::External {
+                    bail!(
+                        "Cannot rename `{}`: it is defined in external package `{}`",
+                        definition.name,
+                        package.name
+                    );
+                }
+                name = Some(definition.name.clone());
+            }
+        }
+
+        match name {
+            Some(name) => Ok(name),
+            None => bail!("Cannot rename a built-in symbol"),
+        }
+    }
+}
+
+/// Whether one scope path encloses the other, e.g `/a.com/M` and `/a.com/M/N`.
+fn scopes_overlap(scope: &str, other_scope: &str) -> bool {
+    let encloses = |outer: &str, inner: &str| {
+        inner
+            .strip_prefix(outer)
+            .is_some_and(|rest| rest.is_empty() || rest.starts_with('/'))
+    };
+    encloses(scope, other_scope) || encloses(other_scope, scope)
+}
+
+fn validate_new_name(name: &str) -> anyhow::Result<()> {
+    let mut chars = name.chars();
+    let valid = chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        bail!("`{name}` is not a valid Verse identifier");
+    }
+    if RESERVED_WORDS.contains(&name) {
+        bail!("`{name}` is a reserved word");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlapping_scopes() {
+        assert!(scopes_overlap("/game/player", "/game/player"));
+        assert!(scopes_overlap("/game", "/game/player"));
+        assert!(scopes_overlap("/game/player", "/game"));
+        assert!(!scopes_overlap("/game/player", "/game/enemy"));
+        assert!(!scopes_overlap("/game/player", "/game/players"));
+    }
+
+    #[test]
+    fn new_name_validation() {
+        assert!(validate_new_name("OnBegin").is_ok());
+        assert!(validate_new_name("_count2").is_ok());
+        assert!(validate_new_name("spawn").is_ok());
+        assert!(validate_new_name("map").is_ok());
+        assert!(validate_new_name("").is_err());
+        assert!(validate_new_name("2count").is_err());
+        assert!(validate_new_name("my-name").is_err());
+        assert!(validate_new_name("if").is_err());
+        assert!(validate_new_name("var").is_err());
+    }
+}
@@ -0,0 +1,60 @@
+use lsp_types::*;
+
+use crate::{ffi, server::LanguageServer};
+
+#[derive(Debug)]
+pub struct SelectionRangeAccumulator {
+    /// Ranges of nodes containing the position, from outermost to innermost.
+    pub spans: Vec<ffi::SSourceSpan>,
+}
+
+impl LanguageServer {
+    pub fn handle_req_selection_range(
+        &self,
+        params: SelectionRangeParams,
+    ) -> anyhow::Result<Option<Vec<SelectionRange>>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let path = self.uri_to_file_path(&params.text_document.uri)?;
+        let path_str = path.to_string_lossy();
+
+        let Some(project_container) = self.find_project_container(&uri) else {
+            return Ok(None);
+        };
+
+        let mut selection_ranges = Vec::with_capacity(params.positions.len());
+        for position in params.positions {
+            let mut acc = SelectionRangeAccumulator { spans: vec![] };
+            crate::get_selection_range(
+                &project_container.c_container,
+                &path_str,
+                position,
+                &mut acc,
+            );
+
+            let mut selection_range: Option<SelectionRange> = None;
+            for span in acc.spans {
+                let range = span.to_range();
+                // nodes wrapping a single child share its range
+                if selection_range
+                    .as_ref()
+                    .is_some_and(|parent| parent.range == range)
+                {
+                    continue;
+                }
+                selection_range = Some(SelectionRange {
+                    range,
+                    parent: selection_range.map(Box::new),
+                });
+            }
+
+            // one result is expected per position
+            selection_ranges.push(selection_range.unwrap_or(SelectionRange {
+                range: Range::new(position, position),
+                parent: None,
+            }));
+        }
+
+        Ok(Some(selection_ranges))
+    }
+}
@@ -142,7 +142,7 @@ impl LanguageServer {
         crate::get_semantic_tokens(
             &project_container.c_container,
             &package.c_package,
-            &path_str,
+            path_str,
             &mut acc,
         );
 
@@ -0,0 +1,101 @@
+use lsp_types::{notification::Notification, *};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    features::project_info::{DiagnosticCounts, diagnostic_counts},
+    server::LanguageServer,
+    verse::ProjectContainer,
+};
+
+/// Custom notification reporting what the server is busy with.
+#[derive(Debug)]
+pub enum StatusNotification {}
+
+impl Notification for StatusNotification {
+    type Params = StatusParams;
+    const METHOD: &'static str = "verseCE/status";
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ServerStatus {
+    /// No message left to process.
+    Idle,
+    /// Processing queued messages.
+    Busy,
+    /// A project started building.
+    BuildStarted,
+    /// A project finished building.
+    BuildFinished,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusParams {
+    pub status: ServerStatus,
+    /// Project the build status is about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<ProjectStatus>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProjectStatus {
+    pub name: String,
+    pub vproject_uri: Url,
+    /// Only set once the build finished.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub duration_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<DiagnosticCounts>,
+}
+
+impl LanguageServer {
+    pub fn send_status(&self, status: ServerStatus) {
+        self.send_notification::<StatusNotification>(StatusParams {
+            status,
+            project: None,
+        });
+    }
+
+    /// Builds a project container, reporting it to the client.
+    pub fn build_project_container(&mut self, index: usize) {
+        let project_container = &self.project_containers[index];
+        self.send_notification::<StatusNotification>(StatusParams {
+            status: ServerStatus::BuildStarted,
+            project: Some(project_status(project_container, false)),
+        });
+
+        self.project_containers[index].build();
+
+        let project_container = &self.project_containers[index];
+        self.send_notification::<StatusNotification>(StatusParams {
+            status: ServerStatus::BuildFinished,
+            project: Some(project_status(project_container, true)),
+        });
+    }
+
+    /// Returns whether any project container was built.
+    pub fn build_pending_project_containers(&mut self) -> bool {
+        let mut any_built = false;
+        for index in 0..self.project_containers.len() {
+            if self.project_containers[index].needs_build {
+                self.build_project_container(index);
+                any_built = true;
+            }
+        }
+        any_built
+    }
+}
+
+fn project_status(project_container: &ProjectContainer, finished: bool) -> ProjectStatus {
+    ProjectStatus {
+        name: project_container.project_name(),
+        vproject_uri: project_container.vproject_uri.clone(),
+        duration_ms: project_container
+            .last_build
+            .filter(|_| finished)
+            .map(|(_, duration)| duration.as_millis() as u64),
+        diagnostics: finished.then(|| diagnostic_counts(project_container)),
+    }
+}
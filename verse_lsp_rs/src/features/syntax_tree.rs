@@ -0,0 +1,106 @@
+use lsp_types::{request::Request, *};
+use serde::{Deserialize, Serialize};
+
+use crate::server::LanguageServer;
+
+/// Custom request returning the VST and AST of a document, for debugging purposes.
+#[derive(Debug)]
+pub enum SyntaxTreeRequest {}
+
+impl Request for SyntaxTreeRequest {
+    type Params = SyntaxTreeParams;
+    type Result = Option<SyntaxTree>;
+    const METHOD: &'static str = "verseCE/syntaxTree";
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxTreeParams {
+    pub text_document: TextDocumentIdentifier,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxTree {
+    pub vst: SyntaxNode,
+    /// Only available once the document went through semantic analysis.
+    pub ast: Option<SyntaxNode>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyntaxNode {
+    pub kind: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// AST nodes without a mapped VST node have no range.
+    pub range: Option<Range>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<SyntaxNode>,
+}
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SyntaxTreeKind {
+    Vst,
+    Ast,
+}
+
+/// Depth-first nodes of both trees, along with their depth.
+#[derive(Debug, Default)]
+pub struct SyntaxTreeAccumulator {
+    pub vst: Vec<(u32, SyntaxNode)>,
+    pub ast: Vec<(u32, SyntaxNode)>,
+}
+
+impl LanguageServer {
+    pub fn handle_req_syntax_tree(
+        &self,
+        params: SyntaxTreeParams,
+    ) -> anyhow::Result<Option<SyntaxTree>> {
+        let uri = self.normalize_uri(&params.text_document.uri)?;
+
+        let path = self.uri_to_file_path(&params.text_document.uri)?;
+        let path_str = path.to_string_lossy();
+
+        let Some(project_container) = self.find_project_container(&uri) else {
+            return Ok(None);
+        };
+
+        let mut acc = SyntaxTreeAccumulator::default();
+        crate::get_syntax_tree(&project_container.c_container, &path_str, &mut acc);
+
+        let Some(vst) = build_tree(acc.vst) else {
+            return Ok(None);
+        };
+        Ok(Some(SyntaxTree {
+            vst,
+            ast: build_tree(acc.ast),
+        }))
+    }
+}
+
+fn build_tree(nodes: Vec<(u32, SyntaxNode)>) -> Option<SyntaxNode> {
+    // ancestors of the last node, each being completed when leaving its depth
+    let mut stack: Vec<SyntaxNode> = vec![];
+    for (depth, node) in nodes {
+        while stack.len() > (depth as usize).max(1) {
+            pop_child(&mut stack);
+        }
+        stack.push(node);
+    }
+    while stack.len() > 1 {
+        pop_child(&mut stack);
+    }
+    stack.pop()
+}
+
+fn pop_child(stack: &mut Vec<SyntaxNode>) {
+    if let Some(child) = stack.pop()
+        && let Some(parent) = stack.last_mut()
+    {
+        parent.children.push(child);
+    }
+}
@@ -0,0 +1,37 @@
+use fxhash::FxHashSet;
+use lsp_types::{
+    request::{GotoTypeDefinitionParams, GotoTypeDefinitionResponse},
+    *,
+};
+
+use crate::server::LanguageServer;
+
+impl LanguageServer {
+    pub fn handle_req_type_definition(
+        &self,
+        params: GotoTypeDefinitionParams,
+    ) -> anyhow::Result<Option<GotoTypeDefinitionResponse>> {
+        let uri = self.normalize_uri(&params.text_document_position_params.text_document.uri)?;
+        let position = params.text_document_position_params.position;
+
+        let mut locations = vec![];
+        let mut seen_locations = FxHashSet::default();
+        for symbol in self.resolve_symbol_at(&uri, position) {
+            let symbol_index = &symbol.project_container.symbol_index;
+
+            let type_names = symbol_index
+                .find_definitions(&symbol.qualified_name)
+                .filter_map(|(_, definition)| definition.type_name.as_ref());
+            for type_name in type_names {
+                for (uri, type_definition) in symbol_index.find_definitions(type_name) {
+                    let location = Location::new(uri.clone(), type_definition.name_range);
+                    if seen_locations.insert(location.clone()) {
+                        locations.push(location);
+                    }
+                }
+            }
+        }
+
+        Ok(Some(GotoDefinitionResponse::Array(locations)))
+    }
+}
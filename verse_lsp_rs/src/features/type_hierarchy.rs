@@ -0,0 +1,108 @@
+use fxhash::FxHashSet;
+use lsp_types::*;
+
+use crate::{
+    server::LanguageServer,
+    symbol_index::{DefinitionKind, SymbolDefinition},
+};
+
+impl LanguageServer {
+    pub fn handle_req_prepare_type_hierarchy(
+        &self,
+        params: TypeHierarchyPrepareParams,
+    ) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+        let uri = self.normalize_uri(&params.text_document_position_params.text_document.uri)?;
+        let position = params.text_document_position_params.position;
+
+        let mut items = vec![];
+        let mut seen_qualified_names = FxHashSet::default();
+        for symbol in self.resolve_symbol_at(&uri, position) {
+            let symbol_index = &symbol.project_container.symbol_index;
+            for (uri, definition) in symbol_index.find_definitions(&symbol.qualified_name) {
+                if is_type_definition(definition)
+                    && seen_qualified_names.insert(definition.qualified_name.clone())
+                {
+                    items.push(type_hierarchy_item(uri, definition));
+                }
+            }
+        }
+
+        Ok(Some(items))
+    }
+
+    pub fn handle_req_supertypes(
+        &self,
+        params: TypeHierarchySupertypesParams,
+    ) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+        let Some(qualified_name) = item_qualified_name(&params.item) else {
+            return Ok(None);
+        };
+
+        let mut items = vec![];
+        let mut seen_qualified_names = FxHashSet::default();
+        for project_container in self.project_containers.iter() {
+            let symbol_index = &project_container.symbol_index;
+            for (_, definition) in symbol_index.find_definitions(qualified_name) {
+                for supertype in definition.supertypes.iter() {
+                    let Some((uri, supertype_definition)) =
+                        symbol_index.find_definitions(supertype).next()
+                    else {
+                        continue;
+                    };
+                    if seen_qualified_names.insert(supertype.clone()) {
+                        items.push(type_hierarchy_item(uri, supertype_definition));
+                    }
+                }
+            }
+        }
+
+        Ok(Some(items))
+    }
+
+    pub fn handle_req_subtypes(
+        &self,
+        params: TypeHierarchySubtypesParams,
+    ) -> anyhow::Result<Option<Vec<TypeHierarchyItem>>> {
+        let Some(qualified_name) = item_qualified_name(&params.item) else {
+            return Ok(None);
+        };
+
+        let mut items = vec![];
+        let mut seen_qualified_names = FxHashSet::default();
+        for project_container in self.project_containers.iter() {
+            let symbol_index = &project_container.symbol_index;
+            for (uri, definition) in symbol_index.find_subtypes(qualified_name) {
+                if seen_qualified_names.insert(definition.qualified_name.clone()) {
+                    items.push(type_hierarchy_item(uri, definition));
+                }
+            }
+        }
+
+        Ok(Some(items))
+    }
+}
+
+fn is_type_definition(definition: &SymbolDefinition) -> bool {
+    matches!(
+        definition.kind,
+        DefinitionKind::Class | DefinitionKind::Struct | DefinitionKind::Interface
+    )
+}
+
+fn item_qualified_name(item: &TypeHierarchyItem) -> Option<&str> {
+    item.data.as_ref()?.as_str()
+}
+
+fn type_hierarchy_item(uri: &Url, definition: &SymbolDefinition) -> TypeHierarchyItem {
+    TypeHierarchyItem {
+        name: definition.name.clone(),
+        kind: definition.kind.to_lsp_symbol_kind(),
+        tags: None,
+        detail: Some(definition.container_name.clone()),
+        uri: uri.clone(),
+        range: definition.range,
+        selection_range: definition.name_range,
+        // resolved again when expanding supertypes/subtypes
+        data: Some(definition.qualified_name.clone().into()),
+    }
+}
@@ -0,0 +1,565 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use fxhash::FxHashMap;
+use lsp_types::*;
+
+use crate::server::LanguageServer;
+use crate::utils;
+use crate::verse::ProjectContainer;
+use crate::vproject::{self, JsonStringValue, PackageRole, VProjectFile, VerseScope};
+
+const NAME_FIELD: [&str; 2] = ["desc", "name"];
+const DIR_PATH_FIELD: [&str; 2] = ["desc", "dirPath"];
+const DEPENDENCY_PACKAGES_FIELD: [&str; 3] = ["desc", "settings", "dependencyPackages"];
+const VERSE_SCOPE_FIELD: [&str; 3] = ["desc", "settings", "verseScope"];
+const ROLE_FIELD: [&str; 3] = ["desc", "settings", "role"];
+const FORTNITE_VERSION_KEY: &str = "\"uploadedAtFNVersion\"";
+
+pub fn capabilities_completion() -> CompletionOptions {
+    CompletionOptions {
+        trigger_characters: Some(vec!["\"".to_owned()]),
+        ..Default::default()
+    }
+}
+
+pub fn is_vproject_uri(uri: &Url) -> bool {
+    uri.path().ends_with(".vproject")
+}
+
+impl LanguageServer {
+    pub fn handle_req_completion(
+        &self,
+        params: CompletionParams,
+    ) -> anyhow::Result<Option<CompletionResponse>> {
+        let position = params.text_document_position.position;
+        let Some(document) =
+            self.vproject_document(&params.text_document_position.text_document)?
+        else {
+            return Ok(None);
+        };
+
+        let Some(value) = document.value_at(position) else {
+            let items = self.fortnite_version_completions(&document, position);
+            return Ok((!items.is_empty()).then_some(CompletionResponse::Array(items)));
+        };
+        let Some((package_index, field)) = value.package_field() else {
+            return Ok(None);
+        };
+
+        // replace the whole string content rather than the word being typed
+        let edit_range = value.content_range;
+        let item =
+            |label: String, kind: CompletionItemKind, detail: Option<String>| CompletionItem {
+                text_edit: Some(CompletionTextEdit::Edit(TextEdit::new(
+                    edit_range,
+                    label.clone(),
+                ))),
+                label,
+                kind: Some(kind),
+                detail,
+                ..Default::default()
+            };
+
+        let items: Vec<CompletionItem> = if field == DEPENDENCY_PACKAGES_FIELD {
+            let Some(package) = document.vproject_file.packages.get(package_index) else {
+                return Ok(None);
+            };
+            document
+                .vproject_file
+                .packages
+                .iter()
+                .filter(|dependency| {
+                    dependency.desc.name != package.desc.name
+                        && (dependency.desc.name == value.value
+                            || !package
+                                .desc
+                                .settings
+                                .dependency_packages
+                                .contains(&dependency.desc.name))
+                })
+                .map(|dependency| {
+                    item(
+                        dependency.desc.name.clone(),
+                        CompletionItemKind::MODULE,
+                        Some(dependency.desc.settings.verse_path.clone()),
+                    )
+                })
+                .collect()
+        } else if field == VERSE_SCOPE_FIELD {
+            enum_variant_names(&VerseScope::ALL)
+                .map(|name| item(name, CompletionItemKind::ENUM_MEMBER, None))
+                .collect()
+        } else if field == ROLE_FIELD {
+            enum_variant_names(&PackageRole::ALL)
+                .map(|name| item(name, CompletionItemKind::ENUM_MEMBER, None))
+                .collect()
+        } else {
+            return Ok(None);
+        };
+        Ok(Some(CompletionResponse::Array(items)))
+    }
+
+    pub fn handle_req_hover(&self, params: HoverParams) -> anyhow::Result<Option<Hover>> {
+        let text_document_position = params.text_document_position_params;
+        let Some(document) = self.vproject_document(&text_document_position.text_document)? else {
+            return Ok(None);
+        };
+        let Some((value, package_index)) =
+            document.package_reference_at(text_document_position.position)
+        else {
+            return Ok(None);
+        };
+        let package = &document.vproject_file.packages[package_index];
+
+        let mut contents = format!(
+            "**{}** `{}`\n\n",
+            package.desc.name, package.desc.settings.verse_path
+        );
+        match package.resolve_dir_path(&document.vproject_path) {
+            Ok(dir_path) => {
+                contents.push_str(&format!("Directory: `{}`\n\n", dir_path.display()));
+                let loaded_package =
+                    self.find_vproject_container(&document.uri)
+                        .and_then(|project_container| {
+                            let loaded_package =
+                                project_container.packages.iter().find(|loaded_package| {
+                                    loaded_package.name == package.desc.name
+                                        && loaded_package.dir_path == dir_path
+                                })?;
+                            Some(project_container.package_file_count(loaded_package))
+                        });
+                match loaded_package {
+                    Some(file_count) => contents.push_str(&format!("Files: {file_count}")),
+                    None => contents.push_str("Not loaded"),
+                }
+            }
+            Err(err) => contents.push_str(&format!("Directory not found: {err:#}")),
+        }
+
+        Ok(Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: contents,
+            }),
+            range: Some(value.range),
+        }))
+    }
+
+    pub fn handle_req_goto_definition(
+        &self,
+        params: GotoDefinitionParams,
+    ) -> anyhow::Result<Option<GotoDefinitionResponse>> {
+        let text_document_position = params.text_document_position_params;
+        let Some(document) = self.vproject_document(&text_document_position.text_document)? else {
+            return Ok(None);
+        };
+        let Some((_, package_index)) =
+            document.package_reference_at(text_document_position.position)
+        else {
+            return Ok(None);
+        };
+
+        let Some(name) = document
+            .values
+            .iter()
+            .find(|value| value.is_package_field(package_index, &NAME_FIELD))
+        else {
+            return Ok(None);
+        };
+        Ok(Some(GotoDefinitionResponse::Scalar(Location::new(
+            text_document_position.text_document.uri,
+            name.range,
+        ))))
+    }
+
+    /// Contents of a .vproject file as edited in the client, or from disk.
+    fn vproject_document(
+        &self,
+        text_document: &TextDocumentIdentifier,
+    ) -> anyhow::Result<Option<VProjectDocument>> {
+        if !is_vproject_uri(&text_document.uri) {
+            return Ok(None);
+        }
+        let vproject_path = self.uri_to_file_path(&text_document.uri)?;
+        let uri = self.normalize_uri(&text_document.uri)?;
+        let contents = match self.vproject_documents.get(&uri) {
+            Some(contents) => contents.clone(),
+            None => fs::read_to_string(&vproject_path)?,
+        };
+
+        // partially written files are still parsed up to the first error
+        let values = vproject::collect_string_values(&contents);
+        let vproject_file = serde_json::from_str(&contents).unwrap_or_else(|_| {
+            self.find_vproject_container(&uri)
+                .map(|project_container| project_container.vproject_file.clone())
+                .unwrap_or(VProjectFile { packages: vec![] })
+        });
+        Ok(Some(VProjectDocument {
+            uri,
+            vproject_path,
+            contents,
+            values,
+            vproject_file,
+        }))
+    }
+
+    fn find_vproject_container(&self, vproject_uri: &Url) -> Option<&ProjectContainer> {
+        self.project_containers.iter().find(|project_container| {
+            project_container.vproject_uri == *vproject_uri
+                || self
+                    .normalize_uri(&project_container.vproject_uri)
+                    .is_ok_and(|uri| uri == *vproject_uri)
+        })
+    }
+
+    /// Fortnite versions used by the document and settings, along with the detected one.
+    fn fortnite_version_completions(
+        &self,
+        document: &VProjectDocument,
+        position: Position,
+    ) -> Vec<CompletionItem> {
+        let Some(line) = document.contents.lines().nth(position.line as usize) else {
+            return vec![];
+        };
+        let prefix: String =
+            char::decode_utf16(line.encode_utf16().take(position.character as usize))
+                .filter_map(Result::ok)
+                .collect();
+        let prefix = prefix.trim_end_matches(|c: char| c.is_ascii_digit() || c.is_whitespace());
+        if !prefix
+            .strip_suffix(':')
+            .is_some_and(|prefix| prefix.trim_end().ends_with(FORTNITE_VERSION_KEY))
+        {
+            return vec![];
+        }
+
+        let mut versions = vec![(self.default_fortnite_version, "Detected Fortnite version")];
+        let configured_versions = self
+            .project_containers
+            .iter()
+            .filter_map(|project_container| project_container.settings.fortnite_version)
+            .map(|version| (version, "Configured Fortnite version"));
+        let used_versions = document
+            .vproject_file
+            .packages
+            .iter()
+            .filter_map(|package| package.desc.settings.fortnite_version)
+            .map(|version| (version, "Used by this project"));
+        for (version, detail) in configured_versions.chain(used_versions) {
+            if !versions.iter().any(|(known, _)| *known == version) {
+                versions.push((version, detail));
+            }
+        }
+
+        versions
+            .into_iter()
+            .map(|(version, detail)| CompletionItem {
+                label: version.to_string(),
+                kind: Some(CompletionItemKind::VALUE),
+                detail: Some(detail.to_owned()),
+                ..Default::default()
+            })
+            .collect()
+    }
+}
+
+struct VProjectDocument {
+    uri: Url,
+    vproject_path: PathBuf,
+    contents: String,
+    values: Vec<JsonStringValue>,
+    vproject_file: VProjectFile,
+}
+
+impl VProjectDocument {
+    fn value_at(&self, position: Position) -> Option<&JsonStringValue> {
+        self.values
+            .iter()
+            .find(|value| utils::range_contains(&value.range, position))
+    }
+
+    /// Package referenced at a position, either by its name, directory or as a dependency.
+    fn package_reference_at(&self, position: Position) -> Option<(&JsonStringValue, usize)> {
+        let value = self.value_at(position)?;
+        let (package_index, field) = value.package_field()?;
+        if field == DEPENDENCY_PACKAGES_FIELD {
+            let package_index = self
+                .vproject_file
+                .packages
+                .iter()
+                .position(|package| package.desc.name == value.value)?;
+            Some((value, package_index))
+        } else if field == NAME_FIELD || field == DIR_PATH_FIELD {
+            (package_index < self.vproject_file.packages.len()).then_some((value, package_index))
+        } else {
+            None
+        }
+    }
+}
+
+fn enum_variant_names<T: serde::Serialize>(variants: &[T]) -> impl Iterator<Item = String> {
+    variants.iter().filter_map(|variant| {
+        serde_json::to_value(variant)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_owned))
+    })
+}
+
+/// Reads a .vproject file and checks it, reporting issues as diagnostics on the file.
+/// A file that can't be read or parsed yields a project without packages.
+pub fn read_vproject_file(vproject_path: &Path) -> (VProjectFile, Vec<Diagnostic>) {
+    let contents = match fs::read_to_string(vproject_path) {
+        Ok(contents) => contents,
+        Err(err) => {
+            let diagnostic = error(Range::default(), format!("Unable to read file: {err}"));
+            return (VProjectFile { packages: vec![] }, vec![diagnostic]);
+        }
+    };
+    let values = vproject::collect_string_values(&contents);
+
+    match serde_json::from_str::<VProjectFile>(&contents) {
+        Ok(vproject_file) => {
+            let diagnostics = check_vproject_file(vproject_path, &vproject_file, &values);
+            (vproject_file, diagnostics)
+        }
+        Err(err) => {
+            let diagnostic = parse_error_diagnostic(&contents, &values, &err);
+            (VProjectFile { packages: vec![] }, vec![diagnostic])
+        }
+    }
+}
+
+/// Points JSON syntax errors at their exact position, and schema errors such as unknown
+/// enum values at the offending string.
+fn parse_error_diagnostic(
+    contents: &str,
+    values: &[JsonStringValue],
+    err: &serde_json::Error,
+) -> Diagnostic {
+    let line_start = contents
+        .split_inclusive('\n')
+        .take(err.line().saturating_sub(1))
+        .map(str::len)
+        .sum::<usize>();
+    let position = utils::offset_to_position(contents, line_start + err.column().saturating_sub(1));
+    let range = values
+        .iter()
+        .find(|value| value.range.end == position)
+        .map(|value| value.range)
+        .unwrap_or(Range::new(position, position));
+
+    let message = err.to_string();
+    let message = match message.rsplit_once(" at line ") {
+        Some((message, _)) => message.to_owned(),
+        None => message,
+    };
+    error(range, format!("Invalid .vproject file: {message}"))
+}
+
+fn check_vproject_file(
+    vproject_path: &Path,
+    vproject_file: &VProjectFile,
+    values: &[JsonStringValue],
+) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let package_field_range = |index: usize, field: &[&str], value: &str| {
+        values
+            .iter()
+            .find(|json_value| {
+                json_value.is_package_field(index, field) && json_value.value == value
+            })
+            .map(|json_value| json_value.range)
+            .unwrap_or_default()
+    };
+
+    let mut package_indices: FxHashMap<&str, usize> = FxHashMap::default();
+    for (index, package) in vproject_file.packages.iter().enumerate() {
+        let name = package.desc.name.as_str();
+        if package_indices.contains_key(name) {
+            diagnostics.push(error(
+                package_field_range(index, &NAME_FIELD, name),
+                format!("Duplicate package name `{name}`"),
+            ));
+        } else {
+            package_indices.insert(name, index);
+        }
+
+        if let Err(err) = package.resolve_dir_path(vproject_path) {
+            diagnostics.push(error(
+                package_field_range(index, &DIR_PATH_FIELD, &package.desc.dir_path),
+                format!("Package `{name}` was not loaded: {err:#}"),
+            ));
+        }
+    }
+
+    for (index, package) in vproject_file.packages.iter().enumerate() {
+        for dependency in package.desc.settings.dependency_packages.iter() {
+            if !package_indices.contains_key(dependency.as_str()) {
+                diagnostics.push(Diagnostic {
+                    severity: Some(DiagnosticSeverity::WARNING),
+                    ..error(
+                        package_field_range(index, &DEPENDENCY_PACKAGES_FIELD, dependency),
+                        format!("Unknown dependency package `{dependency}`"),
+                    )
+                });
+            }
+        }
+    }
+
+    for cycle in find_dependency_cycles(vproject_file, &package_indices) {
+        let (&last, &first) = (cycle.last().unwrap(), cycle.first().unwrap());
+        let names: Vec<&str> = cycle
+            .iter()
+            .chain([&first])
+            .map(|&index| vproject_file.packages[index].desc.name.as_str())
+            .collect();
+        diagnostics.push(error(
+            package_field_range(last, &DEPENDENCY_PACKAGES_FIELD, names[names.len() - 1]),
+            format!("Dependency cycle: {}", names.join(" -> ")),
+        ));
+    }
+
+    diagnostics
+}
+
+/// Package indices of each dependency cycle, in dependency order.
+fn find_dependency_cycles(
+    vproject_file: &VProjectFile,
+    package_indices: &FxHashMap<&str, usize>,
+) -> Vec<Vec<usize>> {
+    #[derive(Clone, Copy, PartialEq)]
+    enum Visit {
+        Pending,
+        InProgress,
+        Done,
+    }
+
+    fn visit(
+        index: usize,
+        vproject_file: &VProjectFile,
+        package_indices: &FxHashMap<&str, usize>,
+        visits: &mut [Visit],
+        stack: &mut Vec<usize>,
+        cycles: &mut Vec<Vec<usize>>,
+    ) {
+        visits[index] = Visit::InProgress;
+        stack.push(index);
+        let dependencies = &vproject_file.packages[index]
+            .desc
+            .settings
+            .dependency_packages;
+        for dependency in dependencies.iter() {
+            let Some(&dependency_index) = package_indices.get(dependency.as_str()) else {
+                continue;
+            };
+            match visits[dependency_index] {
+                Visit::Pending => visit(
+                    dependency_index,
+                    vproject_file,
+                    package_indices,
+                    visits,
+                    stack,
+                    cycles,
+                ),
+                Visit::InProgress => {
+                    let start = stack.iter().position(|&i| i == dependency_index).unwrap();
+                    cycles.push(stack[start..].to_vec());
+                }
+                Visit::Done => {}
+            }
+        }
+        stack.pop();
+        visits[index] = Visit::Done;
+    }
+
+    let mut visits = vec![Visit::Pending; vproject_file.packages.len()];
+    let mut cycles = vec![];
+    for index in 0..vproject_file.packages.len() {
+        if visits[index] == Visit::Pending {
+            visit(
+                index,
+                vproject_file,
+                package_indices,
+                &mut visits,
+                &mut vec![],
+                &mut cycles,
+            );
+        }
+    }
+    cycles
+}
+
+fn error(range: Range, message: String) -> Diagnostic {
+    Diagnostic {
+        range,
+        severity: Some(DiagnosticSeverity::ERROR),
+        source: Some("VerseLspCE".to_owned()),
+        message,
+        ..Default::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::vproject::{PackageDesc, PackageSettings, VProjectPackage};
+
+    use super::*;
+
+    fn vproject_file(packages: &[(&str, &[&str])]) -> VProjectFile {
+        VProjectFile {
+            packages: packages
+                .iter()
+                .map(|(name, dependencies)| VProjectPackage {
+                    desc: PackageDesc {
+                        name: name.to_string(),
+                        dir_path: String::new(),
+                        settings: PackageSettings {
+                            dependency_packages: dependencies
+                                .iter()
+                                .map(|dependency| dependency.to_string())
+                                .collect(),
+                            ..Default::default()
+                        },
+                    },
+                    read_only: false,
+                })
+                .collect(),
+        }
+    }
+
+    fn cycles(packages: &[(&str, &[&str])]) -> Vec<Vec<usize>> {
+        let vproject_file = vproject_file(packages);
+        let package_indices = vproject_file
+            .packages
+            .iter()
+            .enumerate()
+            .map(|(index, package)| (package.desc.name.as_str(), index))
+            .collect();
+        find_dependency_cycles(&vproject_file, &package_indices)
+    }
+
+    #[test]
+    fn no_cycles() {
+        assert!(cycles(&[("A", &["B", "C"]), ("B", &["C"]), ("C", &[])]).is_empty());
+        // unknown packages are reported separately
+        assert!(cycles(&[("A", &["Unknown"])]).is_empty());
+    }
+
+    #[test]
+    fn self_dependency() {
+        assert_eq!(cycles(&[("A", &["A"])]), [[0]]);
+    }
+
+    #[test]
+    fn dependency_cycles() {
+        assert_eq!(
+            cycles(&[("A", &["B"]), ("B", &["C"]), ("C", &["A"])]),
+            [[0, 1, 2]]
+        );
+        assert_eq!(
+            cycles(&[("A", &["B"]), ("B", &["A"]), ("C", &["D"]), ("D", &["C"])]),
+            [[0, 1], [2, 3]]
+        );
+    }
+}
@@ -1,20 +1,31 @@
-use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use anyhow::Context;
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use lsp_server::{Message, Notification};
 use lsp_types::notification::{Notification as _, PublishDiagnostics};
 use lsp_types::{
-    DidChangeTextDocumentParams, DidChangeWorkspaceFoldersParams, OneOf, PublishDiagnosticsParams,
-    Url, WorkspaceFolder, WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidChangeWorkspaceFoldersParams,
+    DidCloseTextDocumentParams, DidOpenTextDocumentParams, FileChangeType, FileEvent,
+    NumberOrString, OneOf, PublishDiagnosticsParams, Url, WorkspaceFolder,
+    WorkspaceFoldersServerCapabilities, WorkspaceServerCapabilities,
 };
 
+use serde::{Deserialize, Serialize};
+
+use crate::features::vproject_file;
 use crate::server::LanguageServer;
+use crate::uefn;
 use crate::utils;
 use crate::verse::{ProjectContainer, SourcePackage};
-use crate::vproject::VProjectFile;
+use crate::vproject::{
+    PackageDesc, PackageRole, PackageSettings, VProjectFile, VProjectPackage, VerseScope,
+};
+
+/// Name of the package of a standalone document, also used as its workspace folder
+/// when the document isn't within one.
+const STANDALONE_PACKAGE_NAME: &str = "Standalone";
 
 pub fn capabilities_workspace_folders() -> WorkspaceServerCapabilities {
     WorkspaceServerCapabilities {
@@ -26,6 +37,43 @@ pub fn capabilities_workspace_folders() -> WorkspaceServerCapabilities {
     }
 }
 
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct DiagnosticsSettings {
+    /// Codes of diagnostics not to report, e.g `3506` for unknown identifiers.
+    pub ignored_codes: Vec<i32>,
+    /// Least severe diagnostics to report, all of them by default.
+    pub min_severity: Option<SeverityFilter>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeverityFilter {
+    Error,
+    Warning,
+    Information,
+    Hint,
+}
+
+impl DiagnosticsSettings {
+    pub fn is_reported(&self, diagnostic: &Diagnostic) -> bool {
+        if let Some(NumberOrString::Number(code)) = diagnostic.code
+            && self.ignored_codes.contains(&code)
+        {
+            return false;
+        }
+
+        let severity = match diagnostic.severity {
+            Some(DiagnosticSeverity::WARNING) => SeverityFilter::Warning,
+            Some(DiagnosticSeverity::INFORMATION) => SeverityFilter::Information,
+            Some(DiagnosticSeverity::HINT) => SeverityFilter::Hint,
+            _ => SeverityFilter::Error,
+        };
+        self.min_severity
+            .is_none_or(|min_severity| severity <= min_severity)
+    }
+}
+
 impl LanguageServer {
     pub fn handle_did_workspace_folders_change(
         &mut self,
@@ -35,25 +83,31 @@ impl LanguageServer {
         for workspace_folder in params.event.removed.iter() {
             let extracted = self
                 .project_containers
-                .extract_if(.., |element| element.workspace_folder.eq(&workspace_folder));
+                .extract_if(.., |element| element.workspace_folder.eq(workspace_folder));
             removed_project_containers.extend(extracted);
 
             self.workspace_folders
-                .retain(|element| element.uri == workspace_folder.uri);
+                .retain(|element| element.uri != workspace_folder.uri);
+            self.folder_settings.remove(&workspace_folder.uri);
+            self.workspace_configs.remove(&workspace_folder.uri);
         }
 
         for project_container in removed_project_containers {
-            // TODO: Remove project container
+            crate::unregister_project_container(project_container.c_container);
+            self.clear_diagnostics(project_container.diagnostics.into_keys());
         }
 
         for workspace_folder in params.event.added.iter() {
-            for vproject_path in self.find_vproject_files(&workspace_folder) {
+            self.load_workspace_config(workspace_folder);
+            for vproject_path in self.find_vproject_files(workspace_folder) {
                 self.register_project_container(vproject_path, workspace_folder.clone());
             }
         }
         self.workspace_folders.extend(params.event.added);
+        self.pull_configuration();
 
         self.publish_diagnostics();
+        self.refresh_inlay_hints();
 
         Ok(())
     }
@@ -72,6 +126,12 @@ impl LanguageServer {
             .context("Expected full document due to FULL sync mode")?
             .text;
 
+        if vproject_file::is_vproject_uri(&change_params.text_document.uri) {
+            let uri = self.normalize_uri(&change_params.text_document.uri)?;
+            self.vproject_documents.insert(uri, contents);
+            return Ok(());
+        }
+
         for project_container in self.project_containers.iter_mut() {
             for package in project_container.packages.clone() {
                 if path.starts_with(&package.dir_path) {
@@ -85,15 +145,145 @@ impl LanguageServer {
         Ok(())
     }
 
-    fn find_vproject_files(&self, workspace_folder: &WorkspaceFolder) -> Vec<PathBuf> {
+    /// Documents outside any .vproject package get a standalone project when opened.
+    pub fn handle_did_document_open(
+        &mut self,
+        open_params: DidOpenTextDocumentParams,
+    ) -> anyhow::Result<()> {
+        let text_document = open_params.text_document;
+        if text_document.language_id != "verse" {
+            return Ok(());
+        }
+        let uri = self.normalize_uri(&text_document.uri)?;
+        let path = self.uri_to_file_path(&text_document.uri)?;
+
+        if self.project_containers.iter().any(|project_container| {
+            project_container
+                .packages
+                .iter()
+                .any(|package| path.starts_with(&package.dir_path))
+        }) {
+            return Ok(());
+        }
+
+        self.register_standalone_project_container(uri, path, &text_document.text);
+        self.publish_diagnostics();
+        Ok(())
+    }
+
+    pub fn handle_did_document_close(
+        &mut self,
+        close_params: DidCloseTextDocumentParams,
+    ) -> anyhow::Result<()> {
+        let uri = self.normalize_uri(&close_params.text_document.uri)?;
+        if vproject_file::is_vproject_uri(&uri) {
+            self.vproject_documents.remove(&uri);
+            return Ok(());
+        }
+
+        if let Some(index) = self
+            .project_containers
+            .iter()
+            .position(|project_container| {
+                project_container.standalone && project_container.vproject_uri == uri
+            })
+        {
+            let removed = self.project_containers.remove(index);
+            crate::unregister_project_container(removed.c_container);
+            self.clear_diagnostics(removed.diagnostics.into_keys());
+            self.drop_unused_standalone_digest_files();
+        }
+        Ok(())
+    }
+
+    pub fn find_vproject_files(&self, workspace_folder: &WorkspaceFolder) -> Vec<PathBuf> {
         if let Ok(path) = workspace_folder.uri.to_file_path() {
-            utils::collect_files_with_extension(&path, "vproject")
+            let settings = self.workspace_settings(workspace_folder);
+            utils::collect_files_with_extension(&path, "vproject", &settings.discovery)
         } else {
             vec![]
         }
     }
 
-    fn register_project_container(
+    /// Registers .vproject files found since settings changed and drops the ones now excluded.
+    pub fn discover_project_containers(&mut self) {
+        let mut vproject_uris = FxHashSet::default();
+        for workspace_folder in self.workspace_folders.clone() {
+            for vproject_path in self.find_vproject_files(&workspace_folder) {
+                let Ok(vproject_uri) = Url::from_file_path(&vproject_path) else {
+                    continue;
+                };
+                if !self
+                    .project_containers
+                    .iter()
+                    .any(|project_container| project_container.vproject_uri == vproject_uri)
+                {
+                    self.register_project_container(vproject_path, workspace_folder.clone());
+                }
+                vproject_uris.insert(vproject_uri);
+            }
+        }
+
+        let removed_project_containers: Vec<ProjectContainer> = self
+            .project_containers
+            .extract_if(.., |project_container| {
+                !project_container.standalone
+                    && !vproject_uris.contains(&project_container.vproject_uri)
+            })
+            .collect();
+        for project_container in removed_project_containers {
+            crate::unregister_project_container(project_container.c_container);
+            self.clear_diagnostics(project_container.diagnostics.into_keys());
+        }
+    }
+
+    /// Reloads project containers of changed .vproject files, picking up created and deleted ones.
+    pub fn handle_vproject_file_changes(&mut self, changes: &[FileEvent]) {
+        let vproject_changes: Vec<&FileEvent> = changes
+            .iter()
+            .filter(|change| change.uri.path().ends_with(".vproject"))
+            .collect();
+        if vproject_changes.is_empty() {
+            return;
+        }
+
+        for change in vproject_changes {
+            if change.typ != FileChangeType::CHANGED {
+                continue;
+            }
+            if let Some(index) = self
+                .project_containers
+                .iter()
+                .position(|project_container| project_container.vproject_uri == change.uri)
+            {
+                self.reload_project_container(index);
+            }
+        }
+        self.discover_project_containers();
+        self.publish_diagnostics();
+    }
+
+    pub fn handle_source_file_deletions(&mut self, changes: &[FileEvent]) {
+        let deleted_uris: Vec<&Url> = changes
+            .iter()
+            .filter(|change| {
+                change.typ == FileChangeType::DELETED && change.uri.path().ends_with(".verse")
+            })
+            .map(|change| &change.uri)
+            .collect();
+        if deleted_uris.is_empty() {
+            return;
+        }
+
+        for project_container in self.project_containers.iter_mut() {
+            for uri in deleted_uris.iter() {
+                project_container.remove_source(uri);
+            }
+        }
+        self.publish_diagnostics();
+    }
+
+    pub fn register_project_container(
         &mut self,
         vproject_path: PathBuf,
         workspace_folder: WorkspaceFolder,
@@ -103,82 +293,328 @@ impl LanguageServer {
             return;
         };
 
-        let read_vproject_file = || -> anyhow::Result<VProjectFile> {
-            let contents = fs::read_to_string(&vproject_path)?;
-            let vproject_file: VProjectFile = serde_json::from_str(&contents)?;
-            Ok(vproject_file)
+        // invalid files still get a project container to report diagnostics on
+        let (vproject_file, vproject_diagnostics) =
+            vproject_file::read_vproject_file(&vproject_path);
+
+        let project_name = vproject_path
+            .file_stem()
+            .map(|file_stem| file_stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let settings = self.project_settings(&workspace_folder, &project_name);
+
+        // reported right away since the project may not get built before files are opened
+        let mut diagnostics = FxHashMap::default();
+        if !vproject_diagnostics.is_empty() {
+            diagnostics.insert(vproject_uri.clone(), vproject_diagnostics.clone());
+        }
+
+        let mut project_container = ProjectContainer {
+            c_container: crate::register_project_container(&workspace_folder.name),
+            workspace_folder,
+            vproject_uri,
+            vproject_file,
+            packages: vec![],
+            settings,
+            diagnostics,
+            vproject_diagnostics,
+            stale_diagnostic_uris: Default::default(),
+            file_cache: Default::default(),
+            dirty_uris: Default::default(),
+            symbol_index: Default::default(),
+            standalone: false,
+            needs_build: false,
+            last_build: None,
         };
-        let vproject_file = match read_vproject_file() {
-            Ok(parsed) => parsed,
-            Err(err) => {
-                log::error!(
-                    "Unable to read/parse .vprojet file to register project container: {err}"
-                );
-                return;
-            }
+        self.register_packages(&mut project_container, &vproject_path);
+        self.remove_covered_standalone_project_containers(&project_container);
+        self.project_containers.push(project_container);
+
+        let index = self.project_containers.len() - 1;
+        self.project_containers[index].load_files_from_disk();
+        self.build_project_container(index);
+    }
+
+    /// Drops standalone projects of documents now belonging to a package of `project_container`,
+    /// which would otherwise be built and report diagnostics twice.
+    fn remove_covered_standalone_project_containers(
+        &mut self,
+        project_container: &ProjectContainer,
+    ) {
+        let removed_project_containers: Vec<ProjectContainer> = self
+            .project_containers
+            .extract_if(.., |standalone_container| {
+                standalone_container.standalone
+                    && utils::uri_to_path(&standalone_container.vproject_uri).is_some_and(|path| {
+                        project_container
+                            .packages
+                            .iter()
+                            .any(|package| path.starts_with(&package.dir_path))
+                    })
+            })
+            .collect();
+        for removed in removed_project_containers {
+            crate::unregister_project_container(removed.c_container);
+            self.clear_diagnostics(removed.diagnostics.into_keys());
+        }
+        self.drop_unused_standalone_digest_files();
+    }
+
+    /// Digests are read from disk again for the next standalone document,
+    /// in case UEFN updated them in the meantime.
+    fn drop_unused_standalone_digest_files(&mut self) {
+        if !self
+            .project_containers
+            .iter()
+            .any(|project_container| project_container.standalone)
+        {
+            self.standalone_digest_files.clear();
+        }
+    }
+
+    /// Gives a document outside any .vproject package an implicit project of its own,
+    /// made of a package with default settings which depends on the standard digests.
+    pub fn register_standalone_project_container(
+        &mut self,
+        uri: Url,
+        path: PathBuf,
+        contents: &str,
+    ) {
+        let digest_packages = self.standard_digest_packages();
+        if digest_packages.is_empty() {
+            log::warn!(
+                "No digest found for standalone document {uri}, neither a .vproject file nor a UEFN installation provides them"
+            );
+        }
+
+        let package = VProjectPackage {
+            desc: PackageDesc {
+                name: STANDALONE_PACKAGE_NAME.to_owned(),
+                dir_path: path.to_string_lossy().into_owned(),
+                settings: PackageSettings {
+                    verse_path: format!("/localhost/{STANDALONE_PACKAGE_NAME}"),
+                    dependency_packages: digest_packages
+                        .iter()
+                        .map(|package| package.desc.name.clone())
+                        .collect(),
+                    ..Default::default()
+                },
+            },
+            read_only: false,
         };
+        let mut packages = digest_packages;
+        packages.push(package);
 
-        let c_container = crate::register_project_container(&workspace_folder.name);
+        let workspace_folder = self
+            .workspace_folders
+            .iter()
+            .find(|workspace_folder| {
+                workspace_folder
+                    .uri
+                    .to_file_path()
+                    .is_ok_and(|dir| path.starts_with(dir))
+            })
+            .cloned()
+            .unwrap_or_else(|| WorkspaceFolder {
+                uri: uri.clone(),
+                name: STANDALONE_PACKAGE_NAME.to_owned(),
+            });
+        let settings = self.workspace_settings(&workspace_folder);
 
-        let mut packages = vec![];
-        for package in vproject_file.packages.iter() {
-            let Ok(dir_path) = PathBuf::from(&package.desc.dir_path).canonicalize() else {
+        let mut project_container = ProjectContainer {
+            c_container: crate::register_project_container(&workspace_folder.name),
+            workspace_folder,
+            vproject_uri: uri,
+            vproject_file: VProjectFile { packages },
+            packages: vec![],
+            settings,
+            diagnostics: Default::default(),
+            vproject_diagnostics: vec![],
+            stale_diagnostic_uris: Default::default(),
+            file_cache: Default::default(),
+            dirty_uris: Default::default(),
+            symbol_index: Default::default(),
+            standalone: true,
+            needs_build: false,
+            last_build: None,
+        };
+        self.register_packages(&mut project_container, &path);
+        self.project_containers.push(project_container);
+
+        let index = self.project_containers.len() - 1;
+        let project_container = &mut self.project_containers[index];
+        for package in project_container.packages.clone() {
+            if !package.read_only {
                 continue;
-            };
+            }
+            let files = self
+                .standalone_digest_files
+                .entry(package.dir_path.clone())
+                .or_insert_with(|| Rc::new(project_container.read_package_files(&package)))
+                .clone();
+            for (path, contents) in files.iter() {
+                project_container.update_source(&package, path, contents);
+            }
+        }
+        if let Some(package) = project_container
+            .packages
+            .last()
+            .filter(|package| !package.read_only)
+            .cloned()
+        {
+            // the document as opened rather than as saved, if it exists on disk at all
+            project_container.update_source(&package, &path, contents);
+        }
+        self.build_project_container(index);
+    }
 
-            let mut package_settings = package.desc.settings.clone();
-            if package_settings.fortnite_version.is_none() {
-                package_settings.fortnite_version = self.settings.fortnite_version;
+    fn register_packages(&self, project_container: &mut ProjectContainer, vproject_path: &Path) {
+        let settings = &project_container.settings;
+        for package in project_container.vproject_file.packages.iter() {
+            if settings.excluded_packages.contains(&package.desc.name) {
+                continue;
             }
+            let dir_path = if project_container.standalone && !package.read_only {
+                // the document itself, which may not be a file
+                PathBuf::from(&package.desc.dir_path)
+            } else {
+                // reported by the .vproject diagnostics
+                let Ok(dir_path) = package.resolve_dir_path(vproject_path) else {
+                    continue;
+                };
+                dir_path
+            };
 
-            let c_package = crate::register_package(
-                &c_container,
+            let package_settings = self.package_settings(settings, package);
+
+            let Some(c_package) = crate::register_package(
+                &project_container.c_container,
                 package.desc.name.as_str(),
-                package.desc.dir_path.as_str(),
+                &dir_path.to_string_lossy(),
                 package.read_only,
                 &package_settings,
-            );
-            packages.push(Rc::new(SourcePackage {
+            ) else {
+                log::error!(
+                    "Too many packages in project {}, skipping package {}",
+                    project_container.project_name(),
+                    package.desc.name
+                );
+                continue;
+            };
+            project_container.packages.push(Rc::new(SourcePackage {
                 name: package.desc.name.clone(),
                 verse_path: package.desc.settings.verse_path.clone(),
                 dir_path,
+                read_only: package.read_only,
+                role: package.desc.settings.role,
+                settings: package_settings,
                 c_package,
             }));
         }
+    }
 
-        let project_container = ProjectContainer {
-            workspace_folder,
-            vproject_uri,
-            vproject_file,
-            c_container,
-            packages,
-            diagnostics: Default::default(),
-            stale_diagnostic_uris: Default::default(),
-            file_cache: Default::default(),
-            needs_build: false,
-        };
-        self.project_containers.push(project_container);
+    /// Verse, UnrealEngine and Fortnite digests of the first .vproject file referencing them,
+    /// with their directory resolved. Falls back to the digests of the local UEFN installation.
+    fn standard_digest_packages(&self) -> Vec<VProjectPackage> {
+        for project_container in self.project_containers.iter() {
+            if project_container.standalone {
+                continue;
+            }
+            let mut digest_packages: Vec<VProjectPackage> = project_container
+                .packages
+                .iter()
+                .filter(|package| {
+                    package.read_only
+                        && uefn::DIGEST_VERSE_PATHS.contains(&package.verse_path.as_str())
+                })
+                .filter_map(|package| {
+                    let mut vproject_package = project_container
+                        .vproject_file
+                        .packages
+                        .iter()
+                        .find(|vproject_package| vproject_package.desc.name == package.name)?
+                        .clone();
+                    vproject_package.desc.dir_path =
+                        package.dir_path.to_string_lossy().into_owned();
+                    Some(vproject_package)
+                })
+                .collect();
+            if digest_packages.is_empty() {
+                continue;
+            }
 
-        let project_container = self.project_containers.last_mut().expect("Just pushed");
-        project_container.load_files_from_disk();
-        project_container.build();
+            let names: Vec<String> = digest_packages
+                .iter()
+                .map(|package| package.desc.name.clone())
+                .collect();
+            for package in digest_packages.iter_mut() {
+                package
+                    .desc
+                    .settings
+                    .dependency_packages
+                    .retain(|dependency| names.contains(dependency));
+            }
+            return digest_packages;
+        }
+
+        let mut names: Vec<String> = vec![];
+        let mut digest_packages = vec![];
+        for digest in uefn::installed_digests() {
+            let name = digest.verse_path.trim_start_matches('/').to_owned();
+            digest_packages.push(VProjectPackage {
+                desc: PackageDesc {
+                    name: name.clone(),
+                    dir_path: digest.path.to_string_lossy().into_owned(),
+                    settings: PackageSettings {
+                        verse_path: digest.verse_path.to_owned(),
+                        verse_scope: VerseScope::PublicAPI,
+                        role: PackageRole::External,
+                        // digests depend on the ones before them, Fortnite on UnrealEngine on Verse
+                        dependency_packages: names.clone(),
+                        ..Default::default()
+                    },
+                },
+                read_only: true,
+            });
+            names.push(name);
+        }
+        digest_packages
+    }
+
+    /// Clears diagnostics of files no project container reports anymore.
+    fn clear_diagnostics(&mut self, uris: impl IntoIterator<Item = Url>) {
+        for uri in uris {
+            if self
+                .project_containers
+                .iter()
+                .any(|project_container| project_container.diagnostics.contains_key(&uri))
+            {
+                continue;
+            }
+            self.send_notification::<PublishDiagnostics>(PublishDiagnosticsParams {
+                uri,
+                diagnostics: vec![],
+                version: None,
+            });
+        }
     }
 
     pub fn publish_diagnostics(&mut self) {
-        let mut all_diagnostics = FxHashMap::default();
+        let mut all_diagnostics: FxHashMap<Url, Vec<Diagnostic>> = FxHashMap::default();
         for project_container in self.project_containers.iter_mut() {
             if !project_container.stale_diagnostic_uris.is_empty() {
                 for stale_uri in std::mem::take(&mut project_container.stale_diagnostic_uris) {
-                    all_diagnostics
-                        .entry(stale_uri.clone())
-                        .or_insert_with(|| vec![]);
+                    all_diagnostics.entry(stale_uri.clone()).or_default();
                 }
             }
+            let diagnostics_settings = &project_container.settings.diagnostics;
             for (uri, diagnostics) in project_container.diagnostics.iter() {
-                all_diagnostics
-                    .entry(uri.clone())
-                    .or_insert_with(|| vec![])
-                    .extend(diagnostics.clone());
+                all_diagnostics.entry(uri.clone()).or_default().extend(
+                    diagnostics
+                        .iter()
+                        .filter(|diagnostic| diagnostics_settings.is_reported(diagnostic))
+                        .cloned(),
+                );
             }
         }
         for (uri, diagnostics) in all_diagnostics {
@@ -0,0 +1,130 @@
+use std::{fs, path::Path};
+
+use lsp_types::{notification::Notification, *};
+
+use crate::server::LanguageServer;
+
+/// Config files mapping onto [`crate::server::VerseLspCESettings`], looked up at the root of workspace folders.
+/// When both exist, the JSON one is used.
+const WORKSPACE_CONFIG_FILE_NAMES: [&str; 2] = [".verselspce.json", ".verselspce.toml"];
+
+impl LanguageServer {
+    /// Config files and .vproject files are reloaded on change for clients able to watch them,
+    /// deleted .verse files are removed from projects.
+    pub fn register_workspace_config_watcher(&mut self) {
+        let dynamic_registration = self
+            .client_capabilities
+            .workspace
+            .as_ref()
+            .and_then(|workspace| workspace.did_change_watched_files.as_ref())
+            .and_then(|did_change| did_change.dynamic_registration)
+            .unwrap_or(false);
+        if !dynamic_registration {
+            return;
+        }
+
+        let register_options = DidChangeWatchedFilesRegistrationOptions {
+            watchers: vec![
+                FileSystemWatcher {
+                    glob_pattern: GlobPattern::String("**/.verselspce.{json,toml}".to_owned()),
+                    kind: None,
+                },
+                FileSystemWatcher {
+                    glob_pattern: GlobPattern::String("**/*.vproject".to_owned()),
+                    kind: None,
+                },
+                FileSystemWatcher {
+                    glob_pattern: GlobPattern::String("**/*.verse".to_owned()),
+                    kind: Some(WatchKind::Delete),
+                },
+            ],
+        };
+        self.send_request::<request::RegisterCapability>(RegistrationParams {
+            registrations: vec![Registration {
+                id: notification::DidChangeWatchedFiles::METHOD.to_owned(),
+                method: notification::DidChangeWatchedFiles::METHOD.to_owned(),
+                register_options: serde_json::to_value(register_options).ok(),
+            }],
+        });
+    }
+
+    pub fn handle_did_change_watched_files(
+        &mut self,
+        params: DidChangeWatchedFilesParams,
+    ) -> anyhow::Result<()> {
+        self.handle_vproject_file_changes(&params.changes);
+        self.handle_source_file_deletions(&params.changes);
+
+        let changed_workspace_folders: Vec<WorkspaceFolder> = self
+            .workspace_folders
+            .iter()
+            .filter(|workspace_folder| {
+                let Ok(dir) = workspace_folder.uri.to_file_path() else {
+                    return false;
+                };
+                params.changes.iter().any(|change| {
+                    change
+                        .uri
+                        .to_file_path()
+                        .is_ok_and(|path| is_workspace_config_file(&dir, &path))
+                })
+            })
+            .cloned()
+            .collect();
+        if changed_workspace_folders.is_empty() {
+            return Ok(());
+        }
+
+        for workspace_folder in changed_workspace_folders.iter() {
+            self.load_workspace_config(workspace_folder);
+        }
+        self.apply_settings();
+        Ok(())
+    }
+
+    pub fn load_workspace_config(&mut self, workspace_folder: &WorkspaceFolder) {
+        let Ok(dir) = workspace_folder.uri.to_file_path() else {
+            return;
+        };
+
+        match read_workspace_config(&dir) {
+            Ok(Some(workspace_config)) => {
+                self.workspace_configs
+                    .insert(workspace_folder.uri.clone(), workspace_config);
+            }
+            Ok(None) => {
+                self.workspace_configs.remove(&workspace_folder.uri);
+            }
+            Err(err) => {
+                // keep the previous config rather than silently falling back to client settings
+                self.send_notification::<notification::ShowMessage>(ShowMessageParams {
+                    typ: MessageType::ERROR,
+                    message: format!("Invalid VerseLspCE config file in {dir:?}: {err}"),
+                });
+            }
+        }
+    }
+}
+
+fn is_workspace_config_file(dir: &Path, path: &Path) -> bool {
+    path.parent() == Some(dir)
+        && path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .is_some_and(|file_name| WORKSPACE_CONFIG_FILE_NAMES.contains(&file_name))
+}
+
+fn read_workspace_config(dir: &Path) -> anyhow::Result<Option<serde_json::Value>> {
+    for file_name in WORKSPACE_CONFIG_FILE_NAMES {
+        let Ok(contents) = fs::read_to_string(dir.join(file_name)) else {
+            continue;
+        };
+        let workspace_config = if file_name.ends_with(".toml") {
+            toml::from_str(&contents)?
+        } else {
+            serde_json::from_str(&contents)?
+        };
+        return Ok(Some(workspace_config));
+    }
+    Ok(None)
+}
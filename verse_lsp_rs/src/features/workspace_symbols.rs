@@ -0,0 +1,48 @@
+use std::cmp::Reverse;
+
+use fxhash::FxHashSet;
+use lsp_types::*;
+
+use crate::{server::LanguageServer, utils};
+
+/// Clients filter the results further as the user types, no need to send everything.
+const MAX_WORKSPACE_SYMBOLS: usize = 256;
+
+impl LanguageServer {
+    pub fn handle_req_workspace_symbol(
+        &self,
+        params: WorkspaceSymbolParams,
+    ) -> anyhow::Result<Option<WorkspaceSymbolResponse>> {
+        let mut matches = vec![];
+        // digest packages are usually shared by all project containers
+        let mut seen_locations = FxHashSet::default();
+        for project_container in self.project_containers.iter() {
+            for (uri, definition) in project_container.symbol_index.definitions() {
+                let Some(score) = utils::fuzzy_match(&params.query, &definition.name) else {
+                    continue;
+                };
+                if !seen_locations.insert((uri, definition.name_range.start)) {
+                    continue;
+                }
+                matches.push((score, uri, definition));
+            }
+        }
+
+        matches.sort_by_key(|(score, _, definition)| (Reverse(*score), definition.name.len()));
+        matches.truncate(MAX_WORKSPACE_SYMBOLS);
+
+        let symbols = matches
+            .into_iter()
+            .map(|(_, uri, definition)| WorkspaceSymbol {
+                name: definition.name.clone(),
+                kind: definition.kind.to_lsp_symbol_kind(),
+                tags: None,
+                container_name: Some(definition.container_name.clone()),
+                location: OneOf::Left(Location::new(uri.clone(), definition.name_range)),
+                data: None,
+            })
+            .collect();
+
+        Ok(Some(WorkspaceSymbolResponse::Nested(symbols)))
+    }
+}
@@ -1,9 +1,23 @@
 use std::ffi::{c_char, c_void};
 
-use crate::{features::semantic_tokens::SemanticTokensAccumulator, verse::DiagnosticAccumulator};
+use lsp_types::{Position, Range};
+
+use crate::{
+    features::{
+        document_links::UsingPathsAccumulator,
+        folding_range::FoldingRangesAccumulator,
+        formatting::{FormattingResult, TextEditsAccumulator},
+        inlay_hints::{InlayHintEntryKind, InlayHintsAccumulator},
+        selection_range::SelectionRangeAccumulator,
+        semantic_tokens::SemanticTokensAccumulator,
+        syntax_tree::{SyntaxTreeAccumulator, SyntaxTreeKind},
+    },
+    symbol_index::{DefinitionKind, SymbolAccumulator},
+    verse::DiagnosticAccumulator,
+};
 
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Clone, Copy, Debug)]
 pub struct SSourceSpan {
     pub begin_row: u32,
     pub begin_col: u32,
@@ -11,6 +25,15 @@ pub struct SSourceSpan {
     pub end_col: u32,
 }
 
+impl SSourceSpan {
+    pub fn to_range(self) -> Range {
+        Range::new(
+            Position::new(self.begin_row, self.begin_col),
+            Position::new(self.end_row, self.end_col),
+        )
+    }
+}
+
 #[repr(C)]
 pub struct LspProjectContainer(c_void);
 
@@ -32,6 +55,21 @@ pub struct SPackageSettings {
     pub allow_experimental: bool,
 }
 
+/// `RsReferenceCode`, reference codes of the diagnostics quick fixes are offered for.
+pub const UNKNOWN_IDENTIFIER_REFERENCE_CODE: u16 = 3506;
+pub const EFFECT_NOT_ALLOWED_REFERENCE_CODE: u16 = 3512;
+
+#[repr(C)]
+pub struct SDiagnosticContext {
+    pub identifier: *const c_char,      // null = None
+    pub missing_effects: *const c_char, // null = None
+    pub has_specifiers_position: bool,
+    pub specifiers_row: u32,
+    pub specifiers_column: u32,
+    pub has_statement_span: bool,
+    pub statement_span: SSourceSpan,
+}
+
 #[repr(C)]
 pub struct SDiagnostic {
     pub path: *const c_char,
@@ -39,6 +77,72 @@ pub struct SDiagnostic {
     pub reference_code: u16,
     pub severity: i32,
     pub span: SSourceSpan,
+    pub context: SDiagnosticContext,
+}
+
+#[repr(C)]
+pub struct SInlayHint {
+    pub kind: InlayHintEntryKind,
+    pub label: *const c_char,
+    pub row: u32,
+    pub column: u32,
+}
+
+#[repr(C)]
+pub struct SFormattingOptions {
+    pub indent_width: u32,
+    pub indent_with_tabs: bool,
+    pub line_length: u32,
+    pub has_range: bool,
+    pub begin_row: u32,
+    pub end_row: u32,
+}
+
+#[repr(C)]
+pub struct STextEdit {
+    pub span: SSourceSpan,
+    pub new_text: *const c_char,
+}
+
+#[repr(C)]
+pub struct SUsingPath {
+    pub path: *const c_char,
+    pub span: SSourceSpan,
+}
+
+#[repr(C)]
+pub struct SSyntaxNode {
+    pub tree: SyntaxTreeKind,
+    pub depth: u32,
+    pub kind: *const c_char,
+    pub field: *const c_char,
+    pub text: *const c_char,
+    pub has_span: bool,
+    pub span: SSourceSpan,
+}
+
+#[repr(C)]
+pub struct SSymbolDefinition {
+    pub qualified_name: *const c_char,
+    pub name: *const c_char,
+    pub container_name: *const c_char,
+    pub kind: DefinitionKind,
+    pub span: SSourceSpan,
+    pub name_span: SSourceSpan,
+    pub supertypes: *const *const c_char,
+    pub supertypes_len: usize,
+    pub overridden_qualified_name: *const c_char,
+    pub type_qualified_name: *const c_char,
+    pub initialized: bool,
+}
+
+#[repr(C)]
+pub struct SSymbolReference {
+    pub qualified_name: *const c_char,
+    pub span: SSourceSpan,
+    pub write: bool,
+    pub call: bool,
+    pub caller_qualified_name: *const c_char,
 }
 
 unsafe extern "C" {
@@ -46,6 +150,8 @@ unsafe extern "C" {
 
     pub fn Lsp_RegisterProjectContainer(project_name: *const c_char) -> *mut LspProjectContainer;
 
+    pub fn Lsp_UnregisterProjectContainer(project_container: *mut LspProjectContainer);
+
     pub fn Lsp_Build(
         project_container: *mut LspProjectContainer,
         diagnostics: *mut DiagnosticAccumulator,
@@ -57,7 +163,9 @@ unsafe extern "C" {
         dir_path: *const c_char,
         read_only: bool,
         settings: SPackageSettings,
-    ) -> *const SPackage;
+    ) -> *const SPackage; // null = too many packages
+
+    pub fn Lsp_UpdatePackageSettings(package: *const SPackage, settings: SPackageSettings);
 
     pub fn Lsp_UnregisterPackage(package: *const SPackage);
 
@@ -68,10 +176,57 @@ unsafe extern "C" {
         contents: *const c_char,
     );
 
+    pub fn Lsp_RemoveSource(package: *const SPackage, path: *const c_char);
+
     pub fn Lsp_SemanticTokens(
         project_container: *mut LspProjectContainer,
         package: *const SPackage,
         path: *const c_char,
         semantic_tokens: *const SemanticTokensAccumulator,
     );
+
+    pub fn Lsp_CollectSymbols(
+        project_container: *mut LspProjectContainer,
+        path: *const c_char,
+        symbols: *mut SymbolAccumulator,
+    ) -> bool;
+
+    pub fn Lsp_FoldingRanges(
+        project_container: *mut LspProjectContainer,
+        path: *const c_char,
+        folding_ranges: *mut FoldingRangesAccumulator,
+    );
+
+    pub fn Lsp_SelectionRange(
+        project_container: *mut LspProjectContainer,
+        path: *const c_char,
+        row: u32,
+        column: u32,
+        selection_range: *mut SelectionRangeAccumulator,
+    );
+
+    pub fn Lsp_InlayHints(
+        project_container: *mut LspProjectContainer,
+        path: *const c_char,
+        inlay_hints: *mut InlayHintsAccumulator,
+    );
+
+    pub fn Lsp_Format(
+        project_container: *mut LspProjectContainer,
+        path: *const c_char,
+        options: SFormattingOptions,
+        text_edits: *mut TextEditsAccumulator,
+    ) -> FormattingResult;
+
+    pub fn Lsp_UsingPaths(
+        project_container: *mut LspProjectContainer,
+        path: *const c_char,
+        using_paths: *mut UsingPathsAccumulator,
+    );
+
+    pub fn Lsp_SyntaxTree(
+        project_container: *mut LspProjectContainer,
+        path: *const c_char,
+        syntax_tree: *mut SyntaxTreeAccumulator,
+    );
 }
@@ -1,12 +1,23 @@
 #![allow(special_module_name, dead_code)]
 
 use std::ffi::{CStr, CString, c_char};
+use std::path::Path;
 
 use crate::{
-    features::semantic_tokens::{SemanticTokenEntry, SemanticTokensAccumulator},
+    features::{
+        code_actions::DiagnosticContext,
+        document_links::UsingPathsAccumulator,
+        folding_range::{FoldingRangeEntry, FoldingRangesAccumulator},
+        formatting::{FormattingResult, TextEditsAccumulator},
+        inlay_hints::{InlayHintEntry, InlayHintsAccumulator},
+        selection_range::SelectionRangeAccumulator,
+        semantic_tokens::{SemanticTokenEntry, SemanticTokensAccumulator},
+        syntax_tree::{SyntaxNode, SyntaxTreeAccumulator, SyntaxTreeKind},
+    },
+    symbol_index::{SymbolAccumulator, SymbolDefinition, SymbolReference},
     verse::{CProjectContainer, CSourcePackage, DiagnosticAccumulator},
 };
-use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, Range, Url};
+use lsp_types::{Diagnostic, DiagnosticSeverity, NumberOrString, Position, TextEdit};
 
 use simple_logger::SimpleLogger;
 
@@ -14,6 +25,8 @@ mod entrypoint;
 mod features;
 mod ffi;
 mod server;
+mod symbol_index;
+mod uefn;
 pub mod utils;
 mod verse;
 mod vproject;
@@ -26,16 +39,17 @@ pub extern "C" fn RS_RunServer() -> i32 {
         .init()
         .unwrap();
 
-    return match entrypoint::main() {
+    match entrypoint::main() {
         Ok(_) => 0,
         Err(err) => {
             log::error!("Server stopped with error: {err}");
             1
         }
-    };
+    }
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn RS_AddDiagnostic(acc: *mut DiagnosticAccumulator, diagnostic: ffi::SDiagnostic) {
     let acc = unsafe { &mut *acc };
 
@@ -45,9 +59,9 @@ pub extern "C" fn RS_AddDiagnostic(acc: *mut DiagnosticAccumulator, diagnostic:
     let path = if path.is_empty() {
         None
     } else {
-        match Url::from_file_path(&path) {
-            Ok(path) => Some(path),
-            Err(_) => {
+        match utils::path_to_uri(Path::new(&path)) {
+            Some(path) => Some(path),
+            None => {
                 log::error!("Couldn't convert path \"{path}\" to url");
                 return;
             }
@@ -58,12 +72,32 @@ pub extern "C" fn RS_AddDiagnostic(acc: *mut DiagnosticAccumulator, diagnostic:
         .to_string_lossy()
         .into_owned();
 
-    let span = diagnostic.span;
+    let context = &diagnostic.context;
+    let context = DiagnosticContext {
+        identifier: (!context.identifier.is_null()).then(|| {
+            unsafe { CStr::from_ptr(context.identifier) }
+                .to_string_lossy()
+                .into_owned()
+        }),
+        missing_effects: if context.missing_effects.is_null() {
+            vec![]
+        } else {
+            unsafe { CStr::from_ptr(context.missing_effects) }
+                .to_string_lossy()
+                .split_whitespace()
+                .map(str::to_owned)
+                .collect()
+        },
+        specifiers_position: context
+            .has_specifiers_position
+            .then(|| Position::new(context.specifiers_row, context.specifiers_column)),
+        statement_range: context
+            .has_statement_span
+            .then(|| context.statement_span.to_range()),
+    };
+
     let diagnostic = Diagnostic {
-        range: Range::new(
-            Position::new(span.begin_row, span.begin_col),
-            Position::new(span.end_row, span.end_col),
-        ),
+        range: diagnostic.span.to_range(),
         severity: Some(match diagnostic.severity {
             1 => DiagnosticSeverity::ERROR,
             2 => DiagnosticSeverity::WARNING,
@@ -77,20 +111,19 @@ pub extern "C" fn RS_AddDiagnostic(acc: *mut DiagnosticAccumulator, diagnostic:
         },
         source: Some("VerseCompiler".to_owned()),
         message,
+        data: serde_json::to_value(context).ok(),
         ..Default::default()
     };
 
     if let Some(path) = path {
-        acc.diagnostics
-            .entry(path)
-            .or_insert_with(|| vec![])
-            .push(diagnostic);
+        acc.diagnostics.entry(path).or_default().push(diagnostic);
     } else {
         acc.global_diagnostics.push(diagnostic);
     }
 }
 
 #[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn RS_AddSemanticToken(
     acc: *mut SemanticTokensAccumulator,
     token_entry: SemanticTokenEntry,
@@ -100,27 +133,207 @@ pub extern "C" fn RS_AddSemanticToken(
     acc.token_entries.push(token_entry);
 }
 
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddFoldingRange(
+    acc: *mut FoldingRangesAccumulator,
+    folding_range_entry: FoldingRangeEntry,
+) {
+    let acc = unsafe { &mut *acc };
+
+    acc.entries.push(folding_range_entry);
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddSelectionRange(
+    acc: *mut SelectionRangeAccumulator,
+    span: ffi::SSourceSpan,
+) {
+    let acc = unsafe { &mut *acc };
+
+    acc.spans.push(span);
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddInlayHint(acc: *mut InlayHintsAccumulator, inlay_hint: ffi::SInlayHint) {
+    let acc = unsafe { &mut *acc };
+
+    let label = unsafe { CStr::from_ptr(inlay_hint.label) }
+        .to_string_lossy()
+        .into_owned();
+    acc.hints.push(InlayHintEntry {
+        kind: inlay_hint.kind,
+        label,
+        position: Position::new(inlay_hint.row, inlay_hint.column),
+    });
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddTextEdit(acc: *mut TextEditsAccumulator, text_edit: ffi::STextEdit) {
+    let acc = unsafe { &mut *acc };
+
+    let new_text = unsafe { CStr::from_ptr(text_edit.new_text) }
+        .to_string_lossy()
+        .into_owned();
+    acc.edits.push(TextEdit {
+        range: text_edit.span.to_range(),
+        new_text,
+    });
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddUsingPath(acc: *mut UsingPathsAccumulator, using_path: ffi::SUsingPath) {
+    let acc = unsafe { &mut *acc };
+
+    let path = unsafe { CStr::from_ptr(using_path.path) }
+        .to_string_lossy()
+        .into_owned();
+    acc.paths.push((path, using_path.span.to_range()));
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddSyntaxNode(acc: *mut SyntaxTreeAccumulator, syntax_node: ffi::SSyntaxNode) {
+    let acc = unsafe { &mut *acc };
+
+    let owned_string = |ptr: *const c_char| {
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    let node = SyntaxNode {
+        kind: owned_string(syntax_node.kind),
+        field: (!syntax_node.field.is_null()).then(|| owned_string(syntax_node.field)),
+        text: (!syntax_node.text.is_null()).then(|| owned_string(syntax_node.text)),
+        range: syntax_node.has_span.then(|| syntax_node.span.to_range()),
+        children: vec![],
+    };
+    match syntax_node.tree {
+        SyntaxTreeKind::Vst => acc.vst.push((syntax_node.depth, node)),
+        SyntaxTreeKind::Ast => acc.ast.push((syntax_node.depth, node)),
+    }
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddSymbolDefinition(
+    acc: *mut SymbolAccumulator,
+    definition: ffi::SSymbolDefinition,
+) {
+    let acc = unsafe { &mut *acc };
+
+    let owned_string = |ptr: *const c_char| {
+        unsafe { CStr::from_ptr(ptr) }
+            .to_string_lossy()
+            .into_owned()
+    };
+    let supertypes = if definition.supertypes_len > 0 {
+        unsafe { std::slice::from_raw_parts(definition.supertypes, definition.supertypes_len) }
+            .iter()
+            .map(|ptr| owned_string(*ptr))
+            .collect()
+    } else {
+        vec![]
+    };
+    acc.definitions.push(SymbolDefinition {
+        qualified_name: owned_string(definition.qualified_name),
+        name: owned_string(definition.name),
+        container_name: owned_string(definition.container_name),
+        kind: definition.kind,
+        range: definition.span.to_range(),
+        name_range: definition.name_span.to_range(),
+        supertypes,
+        overrides: (!definition.overridden_qualified_name.is_null())
+            .then(|| owned_string(definition.overridden_qualified_name)),
+        type_name: (!definition.type_qualified_name.is_null())
+            .then(|| owned_string(definition.type_qualified_name)),
+        initialized: definition.initialized,
+    });
+}
+
+#[unsafe(no_mangle)]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn RS_AddSymbolReference(
+    acc: *mut SymbolAccumulator,
+    reference: ffi::SSymbolReference,
+) {
+    let acc = unsafe { &mut *acc };
+
+    let qualified_name = unsafe { CStr::from_ptr(reference.qualified_name) }
+        .to_string_lossy()
+        .into_owned();
+    let caller = (!reference.caller_qualified_name.is_null()).then(|| {
+        unsafe { CStr::from_ptr(reference.caller_qualified_name) }
+            .to_string_lossy()
+            .into_owned()
+    });
+    acc.references.push(SymbolReference {
+        qualified_name,
+        range: reference.span.to_range(),
+        write: reference.write,
+        call: reference.call,
+        caller,
+    });
+}
+
 pub fn register_project_container(project_name: &str) -> CProjectContainer {
     let c_project_name = CString::new(project_name).unwrap();
     let ptr = unsafe { ffi::Lsp_RegisterProjectContainer(c_project_name.as_ptr()) };
     CProjectContainer(ptr)
 }
 
+/// Frees the cpp project container, packages registered to it can't be used anymore.
+pub fn unregister_project_container(project_container: CProjectContainer) {
+    unsafe {
+        ffi::Lsp_UnregisterProjectContainer(project_container.0);
+    }
+}
+
 pub fn build(project_container: &CProjectContainer, diagnostics: &mut DiagnosticAccumulator) {
     unsafe {
         ffi::Lsp_Build(project_container.0, diagnostics);
     }
 }
 
+/// `None` when the project container can't hold more packages.
 pub fn register_package(
     project_container: &CProjectContainer,
     package_name: &str,
     dir_path: &str,
     read_only: bool,
     settings: &vproject::PackageSettings,
-) -> CSourcePackage {
+) -> Option<CSourcePackage> {
     let c_package_name = CString::new(package_name).unwrap();
     let c_dir_path = CString::new(dir_path).unwrap();
+
+    let ptr = with_c_package_settings(settings, |c_settings| unsafe {
+        ffi::Lsp_RegisterPackage(
+            project_container.0,
+            c_package_name.as_ptr(),
+            c_dir_path.as_ptr(),
+            read_only,
+            c_settings,
+        )
+    });
+    (!ptr.is_null()).then_some(CSourcePackage(ptr))
+}
+
+/// Replaces the settings of a registered package, effective on the next build.
+pub fn update_package_settings(package: &CSourcePackage, settings: &vproject::PackageSettings) {
+    with_c_package_settings(settings, |c_settings| unsafe {
+        ffi::Lsp_UpdatePackageSettings(package.0, c_settings);
+    });
+}
+
+/// C settings borrow strings that only live for the duration of `f`.
+fn with_c_package_settings<R>(
+    settings: &vproject::PackageSettings,
+    f: impl FnOnce(ffi::SPackageSettings) -> R,
+) -> R {
     let s_verse_path = CString::new(settings.verse_path.as_str()).unwrap();
 
     let c_vni_dest_dir = settings
@@ -147,23 +360,14 @@ pub fn register_package(
         treat_modules_as_implicit: settings.treat_modules_as_implicit,
         dependency_packages: c_dependency_packages.as_ptr(),
         dependency_packages_len: settings.dependency_packages.len(),
-        vni_dest_dir: if let Some(vni_dest_dir) = c_vni_dest_dir {
+        vni_dest_dir: if let Some(vni_dest_dir) = &c_vni_dest_dir {
             vni_dest_dir.as_ptr()
         } else {
             std::ptr::null()
         },
         allow_experimental: settings.allow_experimental,
     };
-    let ptr = unsafe {
-        ffi::Lsp_RegisterPackage(
-            project_container.0,
-            c_package_name.as_ptr(),
-            c_dir_path.as_ptr(),
-            read_only,
-            c_settings,
-        )
-    };
-    CSourcePackage(ptr)
+    f(c_settings)
 }
 
 pub fn unregister_package(package: CSourcePackage) {
@@ -191,6 +395,13 @@ pub fn upsert_source(
     };
 }
 
+pub fn remove_source(package: &CSourcePackage, path: &str) {
+    let c_path = CString::new(path).unwrap();
+    unsafe {
+        ffi::Lsp_RemoveSource(package.0, c_path.as_ptr());
+    };
+}
+
 pub fn get_semantic_tokens(
     project_container: &CProjectContainer,
     package: &CSourcePackage,
@@ -207,3 +418,85 @@ pub fn get_semantic_tokens(
         );
     };
 }
+
+/// Returns `false` if the file has no up-to-date AST to collect symbols from.
+pub fn collect_symbols(
+    project_container: &CProjectContainer,
+    path: &str,
+    symbols: &mut SymbolAccumulator,
+) -> bool {
+    let c_path = CString::new(path).unwrap();
+    unsafe { ffi::Lsp_CollectSymbols(project_container.0, c_path.as_ptr(), symbols) }
+}
+
+pub fn get_folding_ranges(
+    project_container: &CProjectContainer,
+    path: &str,
+    folding_ranges: &mut FoldingRangesAccumulator,
+) {
+    let c_path = CString::new(path).unwrap();
+    unsafe {
+        ffi::Lsp_FoldingRanges(project_container.0, c_path.as_ptr(), folding_ranges);
+    };
+}
+
+pub fn get_selection_range(
+    project_container: &CProjectContainer,
+    path: &str,
+    position: Position,
+    selection_range: &mut SelectionRangeAccumulator,
+) {
+    let c_path = CString::new(path).unwrap();
+    unsafe {
+        ffi::Lsp_SelectionRange(
+            project_container.0,
+            c_path.as_ptr(),
+            position.line,
+            position.character,
+            selection_range,
+        );
+    };
+}
+
+pub fn get_inlay_hints(
+    project_container: &CProjectContainer,
+    path: &str,
+    inlay_hints: &mut InlayHintsAccumulator,
+) {
+    let c_path = CString::new(path).unwrap();
+    unsafe {
+        ffi::Lsp_InlayHints(project_container.0, c_path.as_ptr(), inlay_hints);
+    };
+}
+
+pub fn format(
+    project_container: &CProjectContainer,
+    path: &str,
+    options: ffi::SFormattingOptions,
+    text_edits: &mut TextEditsAccumulator,
+) -> FormattingResult {
+    let c_path = CString::new(path).unwrap();
+    unsafe { ffi::Lsp_Format(project_container.0, c_path.as_ptr(), options, text_edits) }
+}
+
+pub fn get_using_paths(
+    project_container: &CProjectContainer,
+    path: &str,
+    using_paths: &mut UsingPathsAccumulator,
+) {
+    let c_path = CString::new(path).unwrap();
+    unsafe {
+        ffi::Lsp_UsingPaths(project_container.0, c_path.as_ptr(), using_paths);
+    };
+}
+
+pub fn get_syntax_tree(
+    project_container: &CProjectContainer,
+    path: &str,
+    syntax_tree: &mut SyntaxTreeAccumulator,
+) {
+    let c_path = CString::new(path).unwrap();
+    unsafe {
+        ffi::Lsp_SyntaxTree(project_container.0, c_path.as_ptr(), syntax_tree);
+    };
+}
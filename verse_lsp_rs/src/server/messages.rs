@@ -9,6 +9,9 @@ use lsp_types::notification::*;
 use lsp_types::request::*;
 use lsp_types::*;
 
+use crate::features::project_info::{ProjectInfoParams, ProjectInfoRequest};
+use crate::features::status::ServerStatus;
+use crate::features::syntax_tree::{SyntaxTreeParams, SyntaxTreeRequest};
 use crate::server::LanguageServer;
 
 macro_rules! message_type_def {
@@ -22,6 +25,8 @@ macro_rules! message_type_def {
     ),*
     $(,)?
 ) => {
+    // variants are named after lsp_types message types
+    #[allow(clippy::enum_variant_names)]
     #[derive(Debug)]
     pub enum $tyname {
     $(
@@ -91,12 +96,41 @@ macro_rules! impl_handlers {
 pub enum ParsedMessage {
     Request(ParsedRequest),
     Notification(ParsedNotification),
+    /// Response to a request sent to the client.
+    Response(Response),
 }
 
 message_type_def!(
     ParsedRequest,
     (lsp_server::Request, lsp_types::request::Request),
     SemanticTokensFullRequest(SemanticTokensParams) => handle_req_semantic_tokens_full,
+    WorkspaceSymbolRequest(WorkspaceSymbolParams) => handle_req_workspace_symbol,
+    References(ReferenceParams) => handle_req_references,
+    PrepareRenameRequest(TextDocumentPositionParams) => handle_req_prepare_rename,
+    Rename(RenameParams) => handle_req_rename,
+    DocumentHighlightRequest(DocumentHighlightParams) => handle_req_document_highlight,
+    FoldingRangeRequest(FoldingRangeParams) => handle_req_folding_range,
+    SelectionRangeRequest(SelectionRangeParams) => handle_req_selection_range,
+    InlayHintRequest(InlayHintParams) => handle_req_inlay_hint,
+    CodeActionRequest(CodeActionParams) => handle_req_code_action,
+    Formatting(DocumentFormattingParams) => handle_req_formatting,
+    RangeFormatting(DocumentRangeFormattingParams) => handle_req_range_formatting,
+    CallHierarchyPrepare(CallHierarchyPrepareParams) => handle_req_prepare_call_hierarchy,
+    CallHierarchyIncomingCalls(CallHierarchyIncomingCallsParams) => handle_req_incoming_calls,
+    CallHierarchyOutgoingCalls(CallHierarchyOutgoingCallsParams) => handle_req_outgoing_calls,
+    TypeHierarchyPrepare(TypeHierarchyPrepareParams) => handle_req_prepare_type_hierarchy,
+    TypeHierarchySupertypes(TypeHierarchySupertypesParams) => handle_req_supertypes,
+    TypeHierarchySubtypes(TypeHierarchySubtypesParams) => handle_req_subtypes,
+    GotoImplementation(GotoImplementationParams) => handle_req_implementation,
+    GotoTypeDefinition(GotoTypeDefinitionParams) => handle_req_type_definition,
+    CodeLensRequest(CodeLensParams) => handle_req_code_lens,
+    CodeLensResolve(CodeLens) => handle_req_code_lens_resolve,
+    DocumentLinkRequest(DocumentLinkParams) => handle_req_document_link,
+    SyntaxTreeRequest(SyntaxTreeParams) => handle_req_syntax_tree,
+    ProjectInfoRequest(Option<ProjectInfoParams>) => handle_req_project_info,
+    Completion(CompletionParams) => handle_req_completion,
+    HoverRequest(HoverParams) => handle_req_hover,
+    GotoDefinition(GotoDefinitionParams) => handle_req_goto_definition,
 );
 
 message_type_def!(
@@ -104,6 +138,10 @@ message_type_def!(
     (lsp_server::Notification, lsp_types::notification::Notification),
     DidChangeWorkspaceFolders(DidChangeWorkspaceFoldersParams) => handle_did_workspace_folders_change,
     DidChangeTextDocument(DidChangeTextDocumentParams) => handle_did_document_change,
+    DidOpenTextDocument(DidOpenTextDocumentParams) => handle_did_document_open,
+    DidCloseTextDocument(DidCloseTextDocumentParams) => handle_did_document_close,
+    DidChangeConfiguration(DidChangeConfigurationParams) => handle_did_change_configuration,
+    DidChangeWatchedFiles(DidChangeWatchedFilesParams) => handle_did_change_watched_files,
 );
 
 #[derive(Debug)]
@@ -134,25 +172,28 @@ pub struct QueuedMessage {
 /// TODO: Extra debouncing based on .build() average time
 pub fn message_processing_worker(mut server: LanguageServer) -> anyhow::Result<()> {
     let message_queue = server.message_queue.clone();
+    let mut busy = false;
     loop {
         let mut queue = message_queue.queue.lock().unwrap();
 
         let msg = queue.pop_front();
+        if msg.is_some() && !busy {
+            busy = true;
+            server.send_status(ServerStatus::Busy);
+        }
 
-        if msg.as_ref().map(|msg| msg.compile_gated).unwrap_or(true) {
-            let mut any_built = false;
-            for project_container in server.project_containers.iter_mut() {
-                if project_container.needs_build {
-                    project_container.build();
-                    any_built = true;
-                }
-            }
-            if any_built {
-                server.publish_diagnostics();
-            }
+        if msg.as_ref().map(|msg| msg.compile_gated).unwrap_or(true)
+            && server.build_pending_project_containers()
+        {
+            server.publish_diagnostics();
+            server.refresh_inlay_hints();
         }
 
         let Some(msg) = msg else {
+            if busy {
+                busy = false;
+                server.send_status(ServerStatus::Idle);
+            }
             while queue.is_empty() {
                 queue = message_queue.condvar.wait(queue).unwrap();
             }
@@ -168,7 +209,7 @@ pub fn message_processing_worker(mut server: LanguageServer) -> anyhow::Result<(
                 let response = match req.route(&mut server) {
                     Ok(result) => Response {
                         id: req_id,
-                        result: result,
+                        result,
                         error: None,
                     },
                     Err(err) => Response {
@@ -184,6 +225,11 @@ pub fn message_processing_worker(mut server: LanguageServer) -> anyhow::Result<(
                     log::error!("Notification error: {err:?}");
                 }
             }
+            ParsedMessage::Response(response) => {
+                if let Err(err) = server.handle_response(response) {
+                    log::error!("Response error: {err:?}");
+                }
+            }
         }
     }
 }
@@ -225,7 +271,7 @@ impl MessageQueue {
             Message::Notification(notification) => {
                 ParsedNotification::parse(notification)?.map(ParsedMessage::Notification)
             }
-            _ => None,
+            Message::Response(response) => Some(ParsedMessage::Response(response)),
         }) else {
             return Ok(());
         };
@@ -238,13 +284,156 @@ impl MessageQueue {
                     uris.push(params.text_document.uri.clone());
                     compile_gated = true;
                 }
-            },
-            ParsedMessage::Notification(notification) => match notification {
-                ParsedNotification::DidChangeTextDocument(params) => {
+                // answered from the index of the last build, a query alone doesn't warrant one
+                ParsedRequest::WorkspaceSymbolRequest(_) => {}
+                ParsedRequest::References(params) => {
+                    uris.push(params.text_document_position.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::PrepareRenameRequest(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::Rename(params) => {
+                    uris.push(params.text_document_position.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::DocumentHighlightRequest(params) => {
+                    uris.push(
+                        params
+                            .text_document_position_params
+                            .text_document
+                            .uri
+                            .clone(),
+                    );
+                    compile_gated = true;
+                }
+                ParsedRequest::FoldingRangeRequest(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::SelectionRangeRequest(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::InlayHintRequest(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::CodeActionRequest(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::Formatting(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::RangeFormatting(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::CallHierarchyPrepare(params) => {
+                    uris.push(
+                        params
+                            .text_document_position_params
+                            .text_document
+                            .uri
+                            .clone(),
+                    );
+                    compile_gated = true;
+                }
+                ParsedRequest::CallHierarchyIncomingCalls(params) => {
+                    uris.push(params.item.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::CallHierarchyOutgoingCalls(params) => {
+                    uris.push(params.item.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::TypeHierarchyPrepare(params) => {
+                    uris.push(
+                        params
+                            .text_document_position_params
+                            .text_document
+                            .uri
+                            .clone(),
+                    );
+                    compile_gated = true;
+                }
+                ParsedRequest::TypeHierarchySupertypes(params) => {
+                    uris.push(params.item.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::TypeHierarchySubtypes(params) => {
+                    uris.push(params.item.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::GotoImplementation(params) => {
+                    uris.push(
+                        params
+                            .text_document_position_params
+                            .text_document
+                            .uri
+                            .clone(),
+                    );
+                    compile_gated = true;
+                }
+                ParsedRequest::GotoTypeDefinition(params) => {
+                    uris.push(
+                        params
+                            .text_document_position_params
+                            .text_document
+                            .uri
+                            .clone(),
+                    );
+                    compile_gated = true;
+                }
+                ParsedRequest::CodeLensRequest(params) => {
                     uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::CodeLensResolve(_) => {
+                    compile_gated = true;
+                }
+                ParsedRequest::DocumentLinkRequest(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::SyntaxTreeRequest(params) => {
+                    uris.push(params.text_document.uri.clone());
+                    compile_gated = true;
+                }
+                ParsedRequest::ProjectInfoRequest(_) => {
+                    compile_gated = true;
+                }
+                ParsedRequest::Completion(params) => {
+                    uris.push(params.text_document_position.text_document.uri.clone());
+                }
+                ParsedRequest::HoverRequest(params) => {
+                    uris.push(
+                        params
+                            .text_document_position_params
+                            .text_document
+                            .uri
+                            .clone(),
+                    );
+                }
+                ParsedRequest::GotoDefinition(params) => {
+                    uris.push(
+                        params
+                            .text_document_position_params
+                            .text_document
+                            .uri
+                            .clone(),
+                    );
                 }
-                _ => {}
             },
+            ParsedMessage::Notification(notification) => {
+                if let ParsedNotification::DidChangeTextDocument(params) = notification {
+                    uris.push(params.text_document.uri.clone());
+                }
+            }
+            ParsedMessage::Response(_) => {}
         }
 
         // TODO: Dedup
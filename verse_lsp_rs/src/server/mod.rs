@@ -1,23 +1,64 @@
-use std::{path::PathBuf, sync::Arc};
+use std::{path::PathBuf, rc::Rc, sync::Arc};
 
-use lsp_server::Connection;
-use lsp_types::{Url, WorkspaceFolder};
+use fxhash::FxHashMap;
+use lsp_server::{Connection, Message, Notification, Request, RequestId, Response};
+use lsp_types::{ClientCapabilities, Url, WorkspaceFolder};
 
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 
-use crate::{server::messages::MessageQueue, verse::ProjectContainer};
+use crate::{
+    features::{
+        formatting::FormattingSettings, inlay_hints::InlayHintsSettings,
+        workspace::DiagnosticsSettings,
+    },
+    server::messages::MessageQueue,
+    uefn,
+    utils::FilesSettings,
+    verse::ProjectContainer,
+};
 
 pub mod messages;
 
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct VerseLspCESettings {
     pub fortnite_version: Option<u32>,
+    #[serde(default)]
+    pub inlay_hints: InlayHintsSettings,
+    #[serde(default)]
+    pub formatting: FormattingSettings,
+    /// Collection of .verse files, from the directory of each package.
+    #[serde(default)]
+    pub files: FilesSettings,
+    /// Discovery of .vproject files, from the workspace folder.
+    #[serde(default)]
+    pub discovery: FilesSettings,
+    /// Names of .vproject packages not to load.
+    #[serde(default)]
+    pub excluded_packages: Vec<String>,
+    #[serde(default)]
+    pub diagnostics: DiagnosticsSettings,
+    /// Settings overridden for a project, keyed by .vproject file name without extension.
+    #[serde(default)]
+    pub projects: FxHashMap<String, serde_json::Value>,
+}
+
+/// Request sent to the client whose response is awaited.
+#[derive(Debug)]
+pub enum PendingRequest {
+    /// Pulled settings, for each workspace folder URI or `None` for global settings.
+    Configuration(Vec<Option<Url>>),
 }
 
 pub struct LanguageServer {
     /// LSP connection.
     pub connection: Arc<Connection>,
+    /// Capabilities advertised by the client on initialization.
+    pub client_capabilities: ClientCapabilities,
+    /// ID of the next request sent to the client.
+    next_request_id: i32,
+    /// Requests sent to the client whose response is handled.
+    pub pending_requests: FxHashMap<RequestId, PendingRequest>,
     /// Workspace folders of the LSP client, unrelated to actual Verse project folders.
     pub workspace_folders: Vec<WorkspaceFolder>,
     /// Each .vproject file gets its own project container, aka. server workspace.
@@ -26,25 +67,50 @@ pub struct LanguageServer {
     /// Messages to process in message loop.
     pub message_queue: Arc<MessageQueue>,
 
+    /// Global settings, from initialization options until pulled from the client.
     pub settings: VerseLspCESettings,
+    /// Settings pulled for each workspace folder.
+    pub folder_settings: FxHashMap<Url, VerseLspCESettings>,
+    /// Raw settings from the config file of each workspace folder, see [`crate::features::workspace_config`].
+    pub workspace_configs: FxHashMap<Url, serde_json::Value>,
+    /// Fortnite version of packages when not specified by settings nor .vproject files.
+    pub default_fortnite_version: u32,
+    /// Contents of .vproject files edited in the client, which may not be saved yet.
+    pub vproject_documents: FxHashMap<Url, String>,
+    /// Files of the digest packages standalone projects depend on, by package directory,
+    /// read once for all of them while any is open.
+    pub standalone_digest_files: FxHashMap<PathBuf, Rc<Vec<(PathBuf, String)>>>,
 }
 
 impl LanguageServer {
     pub fn new(
         connection: Arc<Connection>,
+        client_capabilities: ClientCapabilities,
         message_queue: Arc<MessageQueue>,
         settings: VerseLspCESettings,
     ) -> Self {
         Self {
             connection,
+            client_capabilities,
+            next_request_id: 0,
+            pending_requests: FxHashMap::default(),
             workspace_folders: vec![],
             project_containers: vec![],
             message_queue,
             settings,
+            folder_settings: FxHashMap::default(),
+            workspace_configs: FxHashMap::default(),
+            default_fortnite_version: uefn::FALLBACK_FORTNITE_VERSION,
+            vproject_documents: FxHashMap::default(),
+            standalone_digest_files: FxHashMap::default(),
         }
     }
 
+    /// Canonical path of a file, or the URI itself for documents that aren't files, see [`crate::utils::uri_to_path`].
     pub fn uri_to_file_path(&self, uri: &Url) -> anyhow::Result<PathBuf> {
+        if uri.scheme() != "file" {
+            return Ok(PathBuf::from(uri.as_str()));
+        }
         uri.to_file_path()
             .map_err(|_| anyhow!("Text document URI couldn't be mapped to file path: {uri}"))?
             .canonicalize()
@@ -52,7 +118,60 @@ impl LanguageServer {
     }
 
     pub fn normalize_uri(&self, uri: &Url) -> anyhow::Result<Url> {
+        if uri.scheme() != "file" {
+            return Ok(uri.clone());
+        }
         Url::from_file_path(self.uri_to_file_path(uri)?)
             .map_err(|_| anyhow!("Text document path couldn't be mapped to URI: {uri}"))
     }
+
+    /// Sends a request to the client.
+    /// Its response is ignored unless the returned ID is added to [`Self::pending_requests`].
+    pub fn send_request<R: lsp_types::request::Request>(&mut self, params: R::Params) -> RequestId {
+        let id = RequestId::from(self.next_request_id);
+        self.next_request_id += 1;
+
+        let request = Request::new(id.clone(), R::METHOD.to_owned(), params);
+        if let Err(err) = self.connection.sender.send(Message::Request(request)) {
+            log::error!("Failed to send request {}: {err}", R::METHOD);
+        }
+        id
+    }
+
+    pub fn handle_response(&mut self, response: Response) -> anyhow::Result<()> {
+        let Some(pending_request) = self.pending_requests.remove(&response.id) else {
+            return Ok(());
+        };
+        if let Some(err) = response.error {
+            anyhow::bail!(
+                "Client responded to {pending_request:?} with error: {}",
+                err.message
+            );
+        }
+
+        let result = response.result.unwrap_or_default();
+        match pending_request {
+            PendingRequest::Configuration(scope_uris) => {
+                self.handle_configuration_response(scope_uris, result)
+            }
+        }
+    }
+
+    pub fn send_notification<N: lsp_types::notification::Notification>(&self, params: N::Params) {
+        let notification = Notification::new(N::METHOD.to_owned(), params);
+        if let Err(err) = self
+            .connection
+            .sender
+            .send(Message::Notification(notification))
+        {
+            log::error!("Failed to send notification {}: {err}", N::METHOD);
+        }
+    }
+
+    /// First project container that loaded a file.
+    pub fn find_project_container(&self, uri: &Url) -> Option<&ProjectContainer> {
+        self.project_containers
+            .iter()
+            .find(|project_container| project_container.file_cache.contains_key(uri))
+    }
 }
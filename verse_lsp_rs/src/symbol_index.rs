@@ -0,0 +1,527 @@
+use fxhash::{FxHashMap, FxHashSet};
+use lsp_types::{Position, Range, SymbolKind, Url};
+
+use crate::utils;
+
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DefinitionKind {
+    Module,
+    Class,
+    Struct,
+    Interface,
+    Enum,
+    Function,
+    Data,
+    TypeAlias,
+}
+
+impl DefinitionKind {
+    pub fn to_lsp_symbol_kind(self) -> SymbolKind {
+        match self {
+            Self::Module => SymbolKind::MODULE,
+            Self::Class => SymbolKind::CLASS,
+            Self::Struct => SymbolKind::STRUCT,
+            Self::Interface => SymbolKind::INTERFACE,
+            Self::Enum => SymbolKind::ENUM,
+            Self::Function => SymbolKind::FUNCTION,
+            Self::Data => SymbolKind::VARIABLE,
+            Self::TypeAlias => SymbolKind::TYPE_PARAMETER,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct SymbolDefinition {
+    /// Fully qualified Verse path, suffixed with the definition position for overloads and locals.
+    pub qualified_name: String,
+    pub name: String,
+    /// Verse path of the enclosing scope.
+    pub container_name: String,
+    pub kind: DefinitionKind,
+    /// Range of the whole definition.
+    pub range: Range,
+    /// Range of the defined identifier.
+    pub name_range: Range,
+    /// Qualified names of the parent class and interfaces, for classes and interfaces.
+    pub supertypes: Vec<String>,
+    /// Qualified name of the overridden definition, e.g the interface method a class method implements.
+    pub overrides: Option<String>,
+    /// Qualified name of the class, struct, interface or enum data is of.
+    pub type_name: Option<String>,
+    /// Whether data is defined with a value, e.g `X:int = 0` rather than `X:int`.
+    pub initialized: bool,
+}
+
+/// An identifier resolved to a definition during semantic analysis.
+#[derive(Clone, Debug)]
+pub struct SymbolReference {
+    /// Qualified name of the referenced definition.
+    pub qualified_name: String,
+    pub range: Range,
+    /// Whether the identifier is assigned to, e.g `set X = ...`.
+    pub write: bool,
+    /// Whether the identifier is the callee of an invocation, e.g `X()`.
+    pub call: bool,
+    /// Qualified name of the function the reference is made from.
+    pub caller: Option<String>,
+}
+
+#[derive(Debug, Default)]
+pub struct SymbolAccumulator {
+    pub definitions: Vec<SymbolDefinition>,
+    pub references: Vec<SymbolReference>,
+}
+
+#[derive(Debug, Default)]
+pub struct FileSymbols {
+    pub definitions: Vec<SymbolDefinition>,
+    pub references: Vec<SymbolReference>,
+}
+
+/// Symbols of a project container, indexed per file.
+#[derive(Debug, Default)]
+pub struct SymbolIndex {
+    files: FxHashMap<Url, FileSymbols>,
+    /// Files defining, referencing, inheriting from or overriding a symbol, by qualified name.
+    files_by_symbol: FxHashMap<String, FxHashSet<Url>>,
+    /// Files defining a symbol, by unqualified name.
+    files_by_name: FxHashMap<String, FxHashSet<Url>>,
+}
+
+impl SymbolIndex {
+    /// Replaces the symbols of a file.
+    /// Returns the qualified names of definitions the file gained or lost.
+    pub fn update_file(&mut self, uri: Url, acc: SymbolAccumulator) -> FxHashSet<String> {
+        let previous_names = self.remove_file(&uri);
+        let mut changed_names: FxHashSet<String> = acc
+            .definitions
+            .iter()
+            .map(|definition| definition.qualified_name.clone())
+            .collect();
+        for previous_name in previous_names {
+            if !changed_names.remove(&previous_name) {
+                changed_names.insert(previous_name);
+            }
+        }
+
+        let qualified_names = acc
+            .definitions
+            .iter()
+            .flat_map(|definition| {
+                std::iter::once(&definition.qualified_name)
+                    .chain(definition.supertypes.iter())
+                    .chain(definition.overrides.iter())
+            })
+            .chain(
+                acc.references
+                    .iter()
+                    .map(|reference| &reference.qualified_name),
+            );
+        for qualified_name in qualified_names {
+            self.files_by_symbol
+                .entry(qualified_name.clone())
+                .or_default()
+                .insert(uri.clone());
+        }
+        for definition in acc.definitions.iter() {
+            self.files_by_name
+                .entry(definition.name.clone())
+                .or_default()
+                .insert(uri.clone());
+        }
+
+        self.files.insert(
+            uri,
+            FileSymbols {
+                definitions: acc.definitions,
+                references: acc.references,
+            },
+        );
+        changed_names
+    }
+
+    /// Returns the qualified names of definitions the file had.
+    pub fn remove_file(&mut self, uri: &Url) -> FxHashSet<String> {
+        let Some(file_symbols) = self.files.remove(uri) else {
+            return FxHashSet::default();
+        };
+
+        let qualified_names = file_symbols
+            .definitions
+            .iter()
+            .flat_map(|definition| {
+                std::iter::once(&definition.qualified_name)
+                    .chain(definition.supertypes.iter())
+                    .chain(definition.overrides.iter())
+            })
+            .chain(
+                file_symbols
+                    .references
+                    .iter()
+                    .map(|reference| &reference.qualified_name),
+            );
+        for qualified_name in qualified_names {
+            if let Some(files) = self.files_by_symbol.get_mut(qualified_name) {
+                files.remove(uri);
+                if files.is_empty() {
+                    self.files_by_symbol.remove(qualified_name);
+                }
+            }
+        }
+        for definition in file_symbols.definitions.iter() {
+            if let Some(files) = self.files_by_name.get_mut(&definition.name) {
+                files.remove(uri);
+                if files.is_empty() {
+                    self.files_by_name.remove(&definition.name);
+                }
+            }
+        }
+
+        file_symbols
+            .definitions
+            .into_iter()
+            .map(|definition| definition.qualified_name)
+            .collect()
+    }
+
+    /// Drops symbols of files no longer part of the project, e.g deleted or excluded ones.
+    /// Returns the qualified names of definitions these files had.
+    pub fn retain_files(&mut self, mut keep: impl FnMut(&Url) -> bool) -> FxHashSet<String> {
+        let removed_uris: Vec<Url> = self
+            .files
+            .keys()
+            .filter(|uri| !keep(uri))
+            .cloned()
+            .collect();
+        let mut removed_names = FxHashSet::default();
+        for uri in removed_uris {
+            removed_names.extend(self.remove_file(&uri));
+        }
+        removed_names
+    }
+
+    /// Files defining, referencing, inheriting from or overriding a symbol.
+    pub fn symbol_uris<'a>(&'a self, qualified_name: &str) -> impl Iterator<Item = &'a Url> {
+        self.files_by_symbol
+            .get(qualified_name)
+            .into_iter()
+            .flatten()
+    }
+
+    pub fn file_symbols(&self, uri: &Url) -> Option<&FileSymbols> {
+        self.files.get(uri)
+    }
+
+    pub fn definitions(&self) -> impl Iterator<Item = (&Url, &SymbolDefinition)> {
+        self.files.iter().flat_map(|(uri, file_symbols)| {
+            file_symbols
+                .definitions
+                .iter()
+                .map(move |definition| (uri, definition))
+        })
+    }
+
+    /// Qualified name of the definition or reference at a given position, along with its range.
+    pub fn symbol_at(&self, uri: &Url, position: Position) -> Option<(&str, Range)> {
+        let file_symbols = self.files.get(uri)?;
+
+        let definition = file_symbols
+            .definitions
+            .iter()
+            .find(|definition| utils::range_contains(&definition.name_range, position));
+        if let Some(definition) = definition {
+            return Some((&definition.qualified_name, definition.name_range));
+        }
+
+        file_symbols
+            .references
+            .iter()
+            .filter(|reference| utils::range_contains(&reference.range, position))
+            // nested identifiers, e.g in macro arguments, prefer the innermost one
+            .min_by_key(|reference| utils::range_size(&reference.range))
+            .map(|reference| (reference.qualified_name.as_str(), reference.range))
+    }
+
+    pub fn find_definitions<'a>(
+        &'a self,
+        qualified_name: &'a str,
+    ) -> impl Iterator<Item = (&'a Url, &'a SymbolDefinition)> {
+        self.symbol_files(qualified_name)
+            .flat_map(move |(uri, file_symbols)| {
+                file_symbols
+                    .definitions
+                    .iter()
+                    .filter(move |definition| definition.qualified_name == qualified_name)
+                    .map(move |definition| (uri, definition))
+            })
+    }
+
+    pub fn find_references<'a>(
+        &'a self,
+        qualified_name: &'a str,
+    ) -> impl Iterator<Item = (&'a Url, &'a SymbolReference)> {
+        self.symbol_files(qualified_name)
+            .flat_map(move |(uri, file_symbols)| {
+                file_symbols
+                    .references
+                    .iter()
+                    .filter(move |reference| reference.qualified_name == qualified_name)
+                    .map(move |reference| (uri, reference))
+            })
+    }
+
+    /// Definitions with a given unqualified name, in any scope.
+    pub fn find_definitions_named<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = (&'a Url, &'a SymbolDefinition)> {
+        self.files_by_name
+            .get(name)
+            .into_iter()
+            .flatten()
+            .flat_map(|uri| self.files.get_key_value(uri))
+            .flat_map(move |(uri, file_symbols)| {
+                file_symbols
+                    .definitions
+                    .iter()
+                    .filter(move |definition| definition.name == name)
+                    .map(move |definition| (uri, definition))
+            })
+    }
+
+    /// Classes and interfaces directly inheriting from a class or interface.
+    pub fn find_subtypes<'a>(
+        &'a self,
+        qualified_name: &'a str,
+    ) -> impl Iterator<Item = (&'a Url, &'a SymbolDefinition)> {
+        self.symbol_files(qualified_name)
+            .flat_map(move |(uri, file_symbols)| {
+                file_symbols
+                    .definitions
+                    .iter()
+                    .filter(move |definition| {
+                        definition
+                            .supertypes
+                            .iter()
+                            .any(|supertype| supertype == qualified_name)
+                    })
+                    .map(move |definition| (uri, definition))
+            })
+    }
+
+    /// Definitions directly overriding a definition.
+    pub fn find_overrides<'a>(
+        &'a self,
+        qualified_name: &'a str,
+    ) -> impl Iterator<Item = (&'a Url, &'a SymbolDefinition)> {
+        self.symbol_files(qualified_name)
+            .flat_map(move |(uri, file_symbols)| {
+                file_symbols
+                    .definitions
+                    .iter()
+                    .filter(move |definition| {
+                        definition.overrides.as_deref() == Some(qualified_name)
+                    })
+                    .map(move |definition| (uri, definition))
+            })
+    }
+
+    /// Kind of the definition whose scope contains a definition, e.g `Class` for methods.
+    pub fn enclosing_definition_kind(
+        &self,
+        definition: &SymbolDefinition,
+    ) -> Option<DefinitionKind> {
+        let (scope_path, name) = definition.container_name.rsplit_once('/')?;
+        let qualified_name = format!("{scope_path}.{name}");
+        self.find_definitions(&qualified_name)
+            .next()
+            .map(|(_, enclosing)| enclosing.kind)
+    }
+
+    /// Calls made from the body of a function.
+    pub fn find_calls_from<'a>(
+        &'a self,
+        caller: &'a str,
+    ) -> impl Iterator<Item = (&'a Url, &'a SymbolReference)> {
+        self.symbol_files(caller)
+            .flat_map(move |(uri, file_symbols)| {
+                file_symbols
+                    .references
+                    .iter()
+                    .filter(move |reference| {
+                        reference.call && reference.caller.as_deref() == Some(caller)
+                    })
+                    .map(move |reference| (uri, reference))
+            })
+    }
+
+    fn symbol_files(&self, qualified_name: &str) -> impl Iterator<Item = (&Url, &FileSymbols)> {
+        self.files_by_symbol
+            .get(qualified_name)
+            .into_iter()
+            .flatten()
+            .flat_map(|uri| self.files.get_key_value(uri))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uri(name: &str) -> Url {
+        Url::parse(&format!("file:///project/{name}.verse")).unwrap()
+    }
+
+    fn range(line: u32, start: u32, end: u32) -> Range {
+        Range::new(Position::new(line, start), Position::new(line, end))
+    }
+
+    fn definition(qualified_name: &str, line: u32) -> SymbolDefinition {
+        let (container_name, name) = qualified_name.rsplit_once('.').unwrap();
+        SymbolDefinition {
+            qualified_name: qualified_name.to_owned(),
+            name: name.to_owned(),
+            container_name: container_name.to_owned(),
+            kind: DefinitionKind::Data,
+            range: range(line, 0, 20),
+            name_range: range(line, 0, name.len() as u32),
+            supertypes: vec![],
+            overrides: None,
+            type_name: None,
+            initialized: false,
+        }
+    }
+
+    fn reference(qualified_name: &str, line: u32) -> SymbolReference {
+        SymbolReference {
+            qualified_name: qualified_name.to_owned(),
+            range: range(line, 4, 8),
+            write: false,
+            call: false,
+            caller: None,
+        }
+    }
+
+    fn symbols(
+        definitions: Vec<SymbolDefinition>,
+        references: Vec<SymbolReference>,
+    ) -> SymbolAccumulator {
+        SymbolAccumulator {
+            definitions,
+            references,
+        }
+    }
+
+    #[test]
+    fn find_definitions_and_references() {
+        let mut index = SymbolIndex::default();
+        index.update_file(uri("a"), symbols(vec![definition("/M.X", 0)], vec![]));
+        index.update_file(
+            uri("b"),
+            symbols(vec![], vec![reference("/M.X", 1), reference("/M.Y", 2)]),
+        );
+
+        let definitions: Vec<_> = index.find_definitions("/M.X").collect();
+        assert_eq!(definitions.len(), 1);
+        assert_eq!(definitions[0].0, &uri("a"));
+        let references: Vec<_> = index.find_references("/M.X").collect();
+        assert_eq!(references.len(), 1);
+        assert_eq!(references[0].0, &uri("b"));
+        assert_eq!(index.find_definitions("/M.Y").count(), 0);
+    }
+
+    #[test]
+    fn symbol_at_position() {
+        let mut index = SymbolIndex::default();
+        index.update_file(
+            uri("a"),
+            symbols(vec![definition("/M.Value", 0)], vec![reference("/M.X", 1)]),
+        );
+
+        assert_eq!(
+            index.symbol_at(&uri("a"), Position::new(0, 5)),
+            Some(("/M.Value", range(0, 0, 5)))
+        );
+        assert_eq!(
+            index.symbol_at(&uri("a"), Position::new(1, 6)),
+            Some(("/M.X", range(1, 4, 8)))
+        );
+        assert_eq!(index.symbol_at(&uri("a"), Position::new(1, 0)), None);
+        assert_eq!(index.symbol_at(&uri("b"), Position::new(0, 0)), None);
+    }
+
+    #[test]
+    fn find_definitions_by_name() {
+        let mut index = SymbolIndex::default();
+        index.update_file(
+            uri("a"),
+            symbols(vec![definition("/M.X", 0), definition("/M.Y", 1)], vec![]),
+        );
+        index.update_file(uri("b"), symbols(vec![definition("/N.X", 0)], vec![]));
+
+        let mut names: Vec<_> = index
+            .find_definitions_named("X")
+            .map(|(_, definition)| definition.qualified_name.as_str())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["/M.X", "/N.X"]);
+
+        index.remove_file(&uri("b"));
+        assert_eq!(index.find_definitions_named("X").count(), 1);
+        assert_eq!(index.find_definitions_named("Z").count(), 0);
+    }
+
+    #[test]
+    fn update_replaces_file_symbols() {
+        let mut index = SymbolIndex::default();
+        index.update_file(
+            uri("a"),
+            symbols(vec![definition("/M.X", 0)], vec![reference("/M.Y", 1)]),
+        );
+        index.update_file(uri("a"), symbols(vec![definition("/M.Z", 0)], vec![]));
+
+        assert_eq!(index.find_definitions("/M.X").count(), 0);
+        assert_eq!(index.find_references("/M.Y").count(), 0);
+        assert_eq!(index.find_definitions("/M.Z").count(), 1);
+        assert_eq!(index.symbol_uris("/M.X").count(), 0);
+    }
+
+    #[test]
+    fn update_returns_changed_definitions() {
+        let mut index = SymbolIndex::default();
+        let changed = index.update_file(
+            uri("a"),
+            symbols(vec![definition("/M.X", 0), definition("/M.Y", 1)], vec![]),
+        );
+        assert_eq!(changed.len(), 2);
+
+        // unchanged definitions don't affect other files
+        let changed = index.update_file(
+            uri("a"),
+            symbols(vec![definition("/M.X", 0), definition("/M.Y", 1)], vec![]),
+        );
+        assert!(changed.is_empty());
+
+        let changed = index.update_file(
+            uri("a"),
+            symbols(vec![definition("/M.X", 0), definition("/M.Z", 1)], vec![]),
+        );
+        let mut changed: Vec<String> = changed.into_iter().collect();
+        changed.sort();
+        assert_eq!(changed, ["/M.Y", "/M.Z"]);
+    }
+
+    #[test]
+    fn retain_files_drops_removed_files() {
+        let mut index = SymbolIndex::default();
+        index.update_file(uri("a"), symbols(vec![definition("/M.X", 0)], vec![]));
+        index.update_file(uri("b"), symbols(vec![], vec![reference("/M.X", 0)]));
+
+        let removed = index.retain_files(|uri| uri.path().ends_with("b.verse"));
+        assert_eq!(removed.into_iter().collect::<Vec<_>>(), ["/M.X"]);
+        assert_eq!(index.find_definitions("/M.X").count(), 0);
+        // files referring to removed definitions are still known, to collect them again
+        assert_eq!(index.symbol_uris("/M.X").collect::<Vec<_>>(), [&uri("b")]);
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    env, fs,
+    io::{BufRead, BufReader},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use serde::Deserialize;
+
+/// Used when no local UEFN installation nor digest could be found.
+pub const FALLBACK_FORTNITE_VERSION: u32 = 3811;
+
+/// Verse paths of the digests shipped with UEFN, which standalone documents depend on.
+pub const DIGEST_VERSE_PATHS: [&str; 3] = ["/Verse.org", "/UnrealEngine.com", "/Fortnite.com"];
+
+/// Digest files bundled with UEFN, in dependency order, along with their Verse path.
+const DIGEST_FILES: [(&str, &str); 3] = [
+    ("Verse.digest.verse", "/Verse.org"),
+    ("UnrealEngine.digest.verse", "/UnrealEngine.com"),
+    ("Fortnite.digest.verse", "/Fortnite.com"),
+];
+
+/// Lines of a digest file searched for the build it was generated from.
+const DIGEST_HEADER_LINES: usize = 16;
+
+/// Directory levels searched for digest files within the plugins of an installation.
+const DIGEST_SEARCH_DEPTH: usize = 8;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct InstalledDigest {
+    /// Verse path of the digest package, one of [`DIGEST_VERSE_PATHS`].
+    pub verse_path: &'static str,
+    pub path: PathBuf,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DetectedVersion {
+    /// Fortnite version as expected by `uploadedAtFNVersion`, e.g `3811` for 38.11.
+    pub fortnite_version: u32,
+    /// File the version was read from.
+    pub source: PathBuf,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LauncherInstalled {
+    installation_list: Vec<LauncherInstallation>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct LauncherInstallation {
+    install_location: PathBuf,
+    app_name: String,
+    app_version: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct BuildVersion {
+    branch_name: String,
+}
+
+/// Finds the latest Fortnite version from digest files in `digest_dirs`
+/// and local Fortnite installations, which UEFN is bundled with.
+pub fn detect_fortnite_version(digest_dirs: &[PathBuf]) -> Option<DetectedVersion> {
+    let mut detected: Vec<DetectedVersion> = vec![];
+    for digest_dir in digest_dirs {
+        detected.extend(read_digest_versions(digest_dir));
+    }
+    for install_dir in fortnite_install_dirs() {
+        detected.extend(read_installation_version(&install_dir));
+    }
+    detected
+        .into_iter()
+        .max_by_key(|detected| detected.fortnite_version)
+}
+
+/// Digest files of the local Fortnite installation, for documents no .vproject file brings digests to.
+/// The installation is only searched once.
+pub fn installed_digests() -> &'static [InstalledDigest] {
+    static INSTALLED_DIGESTS: OnceLock<Vec<InstalledDigest>> = OnceLock::new();
+    INSTALLED_DIGESTS.get_or_init(|| {
+        let mut digests = vec![];
+        for install_dir in fortnite_install_dirs() {
+            find_digest_files(
+                &install_dir.join("FortniteGame/Plugins"),
+                DIGEST_SEARCH_DEPTH,
+                &mut digests,
+            );
+            if !digests.is_empty() {
+                break;
+            }
+        }
+        digests.sort_by_key(|digest| {
+            DIGEST_VERSE_PATHS
+                .iter()
+                .position(|verse_path| *verse_path == digest.verse_path)
+        });
+        digests
+    })
+}
+
+fn find_digest_files(dir: &Path, depth: usize, digests: &mut Vec<InstalledDigest>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if path.is_dir() {
+            if depth > 0 {
+                find_digest_files(&path, depth - 1, digests);
+            }
+            continue;
+        }
+        let Some(file_name) = path.file_name().and_then(|file_name| file_name.to_str()) else {
+            continue;
+        };
+        if let Some((_, verse_path)) = DIGEST_FILES.iter().find(|(name, _)| *name == file_name)
+            && !digests
+                .iter()
+                .any(|digest| digest.verse_path == *verse_path)
+        {
+            digests.push(InstalledDigest { verse_path, path });
+        }
+    }
+}
+
+fn read_digest_versions(dir: &Path) -> Vec<DetectedVersion> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|file_name| file_name.to_str())
+                .is_some_and(|file_name| file_name.ends_with(".digest.verse"))
+        })
+        .filter_map(|path| {
+            // e.g `# Generated from build: ++Fortnite+Release-38.11-CL-47207400`
+            let file = fs::File::open(&path).ok()?;
+            let fortnite_version = BufReader::new(file)
+                .lines()
+                .take(DIGEST_HEADER_LINES)
+                .map_while(Result::ok)
+                .find_map(|line| parse_release_version(&line))?;
+            Some(DetectedVersion {
+                fortnite_version,
+                source: path,
+            })
+        })
+        .collect()
+}
+
+fn read_installation_version(install_dir: &Path) -> Option<DetectedVersion> {
+    let build_version_path = install_dir.join("Engine/Build/Build.version");
+    let contents = fs::read_to_string(&build_version_path).ok()?;
+    let build_version: BuildVersion = serde_json::from_str(&contents).ok()?;
+    Some(DetectedVersion {
+        fortnite_version: parse_release_version(&build_version.branch_name)?,
+        source: build_version_path,
+    })
+}
+
+/// Fortnite installations known to the Epic Games Launcher, along with the default location.
+fn fortnite_install_dirs() -> Vec<PathBuf> {
+    let mut install_dirs = vec![];
+    if let Some(program_data) = env::var_os("ProgramData") {
+        let launcher_installed_path =
+            Path::new(&program_data).join("Epic/UnrealEngineLauncher/LauncherInstalled.dat");
+        if let Ok(contents) = fs::read_to_string(launcher_installed_path)
+            && let Ok(launcher_installed) = serde_json::from_str::<LauncherInstalled>(&contents)
+        {
+            install_dirs.extend(
+                launcher_installed
+                    .installation_list
+                    .into_iter()
+                    .filter(|installation| installation.app_name == "Fortnite")
+                    .inspect(|installation| {
+                        log::debug!(
+                            "Found Fortnite {} in launcher installations",
+                            installation.app_version
+                        );
+                    })
+                    .map(|installation| installation.install_location),
+            );
+        }
+    }
+    if let Some(program_files) = env::var_os("ProgramFiles") {
+        let default_install_dir = Path::new(&program_files).join("Epic Games/Fortnite");
+        if !install_dirs.contains(&default_install_dir) {
+            install_dirs.push(default_install_dir);
+        }
+    }
+    install_dirs
+}
+
+/// Parses `38.11` out of `++Fortnite+Release-38.11-CL-47207400` as `3811`.
+fn parse_release_version(text: &str) -> Option<u32> {
+    let (_, release) = text.split_once("Release-")?;
+    let release = release.split(['-', ' ']).next()?.trim();
+    let (major, minor) = release.split_once('.')?;
+    let major: u32 = major.parse().ok()?;
+    let minor: u32 = minor.parse().ok()?;
+    Some(major * 100 + minor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_release_versions() {
+        assert_eq!(
+            parse_release_version("++Fortnite+Release-38.11-CL-47207400"),
+            Some(3811)
+        );
+        assert_eq!(
+            parse_release_version("# Generated from build: ++Fortnite+Release-37.00-CL-1"),
+            Some(3700)
+        );
+        assert_eq!(parse_release_version("Release-38.11 (hotfix)"), Some(3811));
+    }
+
+    #[test]
+    fn parse_invalid_release_versions() {
+        assert_eq!(parse_release_version("++Fortnite+Main-CL-47207400"), None);
+        assert_eq!(parse_release_version("++Fortnite+Release-38-CL-1"), None);
+        assert_eq!(parse_release_version("++Fortnite+Release-a.b-CL-1"), None);
+        assert_eq!(parse_release_version(""), None);
+    }
+}
@@ -3,7 +3,9 @@ use std::{
     sync::{Arc, Mutex},
 };
 
-use ignore::{WalkBuilder, WalkState};
+use ignore::{WalkBuilder, WalkState, overrides::OverrideBuilder};
+use lsp_types::{Position, Range, Url};
+use serde::{Deserialize, Serialize};
 
 #[macro_export]
 macro_rules! profile {
@@ -19,28 +21,81 @@ macro_rules! profile {
     };
 }
 
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct FilesSettings {
+    /// Globs of files and directories to skip, relative to the traversed directory.
+    pub exclude: Vec<String>,
+    /// Names of directories to skip wherever they are, replaces the default list when set.
+    pub skip_dirs: Vec<String>,
+    /// Whether to skip files matched by `.gitignore` and `.ignore` files.
+    pub respect_ignore_files: bool,
+    /// How many directories deep to traverse from the traversed directory, unlimited by default.
+    pub max_depth: Option<usize>,
+}
+
+impl Default for FilesSettings {
+    fn default() -> Self {
+        Self {
+            exclude: vec![],
+            // speeds things up for big projects where __ExternalActors__ is massive
+            skip_dirs: [".git", ".urc", "__ExternalActors__", "__ExternalObjects__"]
+                .map(String::from)
+                .to_vec(),
+            respect_ignore_files: false,
+            max_depth: None,
+        }
+    }
+}
+
 /// Traverses a path to collect all files with a given extension.
 /// Uses parallel traversal.
-pub fn collect_files_with_extension(path: &Path, file_extension: &str) -> Vec<PathBuf> {
+pub fn collect_files_with_extension(
+    path: &Path,
+    file_extension: &str,
+    settings: &FilesSettings,
+) -> Vec<PathBuf> {
     let result_paths = Arc::new(Mutex::new(Vec::new()));
 
-    let walker = WalkBuilder::new(path)
+    let mut overrides = OverrideBuilder::new(path);
+    for glob in settings.exclude.iter() {
+        if let Err(err) = overrides.add(&format!("!{glob}")) {
+            log::error!("Invalid exclude glob \"{glob}\": {err}");
+        }
+    }
+
+    let mut walker = WalkBuilder::new(path);
+    walker
         .standard_filters(false)
-        .follow_links(true)
-        .build_parallel();
+        .git_ignore(settings.respect_ignore_files)
+        .ignore(settings.respect_ignore_files)
+        .require_git(false)
+        .max_depth(settings.max_depth)
+        .follow_links(true);
+    match overrides.build() {
+        Ok(overrides) => {
+            walker.overrides(overrides);
+        }
+        Err(err) => log::error!("Invalid exclude globs: {err}"),
+    }
+    let walker = walker.build_parallel();
     walker.run(|| {
         let result_paths = result_paths.clone();
+        let skip_dirs = &settings.skip_dirs;
 
         Box::new(move |result| {
             let Ok(dir_entry) = result else {
                 return WalkState::Continue;
             };
 
-            // speeds things up for big projects where __ExternalActors__ is massive
-            if matches!(
-                dir_entry.file_name().to_str(),
-                Some(".git" | ".urc" | "__ExternalActors__" | "__ExternalObjects__")
-            ) {
+            if dir_entry
+                .file_type()
+                .is_some_and(|file_type| file_type.is_dir())
+                && dir_entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| skip_dirs.iter().any(|skip_dir| skip_dir == name))
+            {
                 return WalkState::Skip;
             }
 
@@ -61,3 +116,130 @@ pub fn collect_files_with_extension(path: &Path, file_extension: &str) -> Vec<Pa
         .into_inner()
         .unwrap_or_default()
 }
+
+/// Matches `pattern` as a case-insensitive subsequence of `candidate`.
+/// Consecutive characters and characters at word boundaries score higher.
+/// Returns `None` if `candidate` doesn't contain all characters of `pattern`.
+pub fn fuzzy_match(pattern: &str, candidate: &str) -> Option<i32> {
+    let mut pattern_chars = pattern.chars().filter(|c| !c.is_whitespace()).peekable();
+
+    let mut score = 0;
+    let mut prev_char: Option<char> = None;
+    let mut prev_matched = false;
+    for c in candidate.chars() {
+        let Some(&expected) = pattern_chars.peek() else {
+            break;
+        };
+
+        if c.to_lowercase().eq(expected.to_lowercase()) {
+            score += 1;
+            if c == expected {
+                score += 1;
+            }
+            if prev_matched {
+                score += 4;
+            }
+            let word_boundary = match prev_char {
+                Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && c.is_uppercase()),
+                None => true,
+            };
+            if word_boundary {
+                score += 3;
+            }
+
+            pattern_chars.next();
+            prev_matched = true;
+        } else {
+            prev_matched = false;
+        }
+        prev_char = Some(c);
+    }
+
+    if pattern_chars.peek().is_some() {
+        None
+    } else {
+        Some(score)
+    }
+}
+
+/// Whether `position` is within `range`, including its end so that
+/// a cursor placed right after an identifier still targets it.
+pub fn range_contains(range: &Range, position: Position) -> bool {
+    range.start <= position && position <= range.end
+}
+
+/// Rough size of a range, only meant to compare ranges that contain each other.
+pub fn range_size(range: &Range) -> (u32, u32) {
+    let lines = range.end.line - range.start.line;
+    if lines == 0 {
+        (0, range.end.character - range.start.character)
+    } else {
+        (lines, range.end.character)
+    }
+}
+
+/// Converts a byte offset of `text` to an LSP position, counting UTF-16 code units.
+pub fn offset_to_position(text: &str, offset: usize) -> Position {
+    let mut offset = offset.min(text.len());
+    while !text.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &text[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position::new(
+        before.matches('\n').count() as u32,
+        before[line_start..].encode_utf16().count() as u32,
+    )
+}
+
+/// Path of a document as known by the compiler.
+/// Documents that aren't files, e.g `untitled:` buffers, are identified by their URI instead.
+pub fn uri_to_path(uri: &Url) -> Option<PathBuf> {
+    if uri.scheme() == "file" {
+        uri.to_file_path().ok()
+    } else {
+        Some(PathBuf::from(uri.as_str()))
+    }
+}
+
+/// Reverse of [`uri_to_path`].
+pub fn path_to_uri(path: &Path) -> Option<Url> {
+    if path.is_absolute() {
+        Url::from_file_path(path).ok()
+    } else {
+        Url::parse(&path.to_string_lossy()).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_match_subsequence() {
+        assert!(fuzzy_match("tk", "tick").is_some());
+        assert!(fuzzy_match("TICK", "tick").is_some());
+        assert!(fuzzy_match("ki", "tick").is_none());
+        assert!(fuzzy_match("ticks", "tick").is_none());
+        assert_eq!(fuzzy_match("", "tick"), Some(0));
+    }
+
+    #[test]
+    fn fuzzy_match_ignores_whitespace() {
+        assert_eq!(
+            fuzzy_match("on begin", "OnBegin"),
+            fuzzy_match("onbegin", "OnBegin")
+        );
+    }
+
+    #[test]
+    fn fuzzy_match_scores() {
+        let score = |pattern, candidate| fuzzy_match(pattern, candidate).unwrap();
+        // consecutive characters
+        assert!(score("beg", "OnBegin") > score("beg", "OnBxexg"));
+        // word boundaries
+        assert!(score("ob", "on_begin") > score("ob", "oxbx"));
+        // exact case
+        assert!(score("On", "OnBegin") > score("on", "OnBegin"));
+    }
+}
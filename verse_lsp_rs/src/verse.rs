@@ -3,16 +3,27 @@ use std::{
     fs,
     path::{Path, PathBuf},
     rc::Rc,
+    time::{Duration, Instant, SystemTime},
 };
 
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
 use lsp_types::{Diagnostic, Url, WorkspaceFolder};
 
-use crate::{ffi, profile, utils, vproject::VProjectFile};
+use crate::{
+    ffi, profile,
+    server::VerseLspCESettings,
+    symbol_index::{SymbolAccumulator, SymbolIndex},
+    utils,
+    vproject::{PackageRole, PackageSettings, VProjectFile},
+};
 
 #[derive(Debug, Clone)]
 pub struct FileState {
     pub span_source: SpanSource,
+    /// Contents as last given to the compiler, which may differ from the file on disk.
+    pub text: String,
+    /// Path the file is registered with in the compiler.
+    pub path: PathBuf,
 }
 
 #[derive(Debug, Clone)]
@@ -37,15 +48,33 @@ pub struct ProjectContainer {
     pub c_container: CProjectContainer,
     /// Packages.
     pub packages: Vec<Rc<SourcePackage>>,
+    /// Settings resolved for this project, see [`LanguageServer::project_settings`].
+    ///
+    /// [`LanguageServer::project_settings`]: crate::server::LanguageServer::project_settings
+    pub settings: VerseLspCESettings,
 
+    /// Diagnostics about the .vproject file itself, reported along with build diagnostics.
+    pub vproject_diagnostics: Vec<Diagnostic>,
     /// Diagnostics from the last build attempt.
     pub diagnostics: FxHashMap<Url, Vec<Diagnostic>>,
     /// Files that need to be cleared of diagnostics.
     pub stale_diagnostic_uris: HashSet<Url>,
 
     pub file_cache: FxHashMap<Url, FileState>,
+    /// Files updated since the last build, whose symbols need to be collected again.
+    pub dirty_uris: HashSet<Url>,
+    /// Definitions from all packages, updated after each build.
+    pub symbol_index: SymbolIndex,
+
+    /// Implicit project of a document outside any .vproject package, bound to the document URI
+    /// rather than a .vproject file. See [`LanguageServer::register_standalone_project_container`].
+    ///
+    /// [`LanguageServer::register_standalone_project_container`]: crate::server::LanguageServer::register_standalone_project_container
+    pub standalone: bool,
 
     pub needs_build: bool,
+    /// When the last build finished, along with how long it took.
+    pub last_build: Option<(SystemTime, Duration)>,
 }
 
 #[derive(Debug)]
@@ -56,6 +85,10 @@ pub struct SourcePackage {
     pub name: String,
     pub verse_path: String,
     pub dir_path: PathBuf,
+    pub read_only: bool,
+    pub role: PackageRole,
+    /// Settings registered with the package, after applying server settings.
+    pub settings: PackageSettings,
     pub c_package: CSourcePackage,
 }
 
@@ -91,32 +124,107 @@ impl SpanSource {
 }
 
 impl ProjectContainer {
+    /// Name of the .vproject file, without extension.
+    pub fn project_name(&self) -> String {
+        self.vproject_uri
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .map(|file_name| file_name.trim_end_matches(".vproject").to_owned())
+            .unwrap_or_else(|| self.workspace_folder.name.clone())
+    }
+
+    /// Package a file belongs to.
+    pub fn find_package(&self, uri: &Url) -> Option<&Rc<SourcePackage>> {
+        let path = utils::uri_to_path(uri)?;
+        self.packages
+            .iter()
+            .find(|package| path.starts_with(&package.dir_path))
+    }
+
+    /// Number of loaded files belonging to a package.
+    pub fn package_file_count(&self, package: &SourcePackage) -> usize {
+        self.file_cache
+            .values()
+            .filter(|file_state| file_state.path.starts_with(&package.dir_path))
+            .count()
+    }
+
     pub fn build(&mut self) {
         let mut diagnostic_acc = DiagnosticAccumulator {
             global_diagnostics: vec![],
             diagnostics: FxHashMap::default(),
         };
 
+        let start = Instant::now();
         profile! {
             format!("Build project {}", &self.vproject_uri.as_str()),
             crate::build(&self.c_container, &mut diagnostic_acc);
         };
         self.needs_build = false;
+        self.last_build = Some((SystemTime::now(), start.elapsed()));
 
         let mut stale_diagnostic_uris = HashSet::with_capacity(self.diagnostics.len());
         stale_diagnostic_uris.extend(self.diagnostics.keys().cloned());
 
         self.diagnostics = diagnostic_acc.diagnostics;
 
-        if !diagnostic_acc.global_diagnostics.is_empty() {
+        if !diagnostic_acc.global_diagnostics.is_empty() || !self.vproject_diagnostics.is_empty() {
             self.diagnostics
                 .entry(self.vproject_uri.clone())
-                .or_insert_with(|| vec![])
-                .extend(diagnostic_acc.global_diagnostics);
+                .or_default()
+                .extend(
+                    self.vproject_diagnostics
+                        .iter()
+                        .cloned()
+                        .chain(diagnostic_acc.global_diagnostics),
+                );
         }
 
-        stale_diagnostic_uris.retain(|uri| !self.diagnostics.contains_key(&uri));
+        stale_diagnostic_uris.retain(|uri| !self.diagnostics.contains_key(uri));
         self.stale_diagnostic_uris.extend(stale_diagnostic_uris);
+
+        profile! {
+            format!("Update symbol index of project {}", &self.vproject_uri.as_str()),
+            self.update_symbol_index();
+        };
+    }
+
+    /// Collects symbols of files updated since the last build, then of files referring to
+    /// definitions these updates added or removed since their references may resolve differently.
+    fn update_symbol_index(&mut self) {
+        let mut changed_names = self
+            .symbol_index
+            .retain_files(|uri| self.file_cache.contains_key(uri));
+
+        let dirty_uris = std::mem::take(&mut self.dirty_uris);
+        for uri in dirty_uris.iter() {
+            changed_names.extend(self.collect_file_symbols(uri));
+        }
+
+        let dependent_uris: FxHashSet<Url> = changed_names
+            .iter()
+            .flat_map(|qualified_name| self.symbol_index.symbol_uris(qualified_name))
+            .filter(|uri| !dirty_uris.contains(*uri))
+            .cloned()
+            .collect();
+        for uri in dependent_uris.iter() {
+            self.collect_file_symbols(uri);
+        }
+    }
+
+    /// Returns the qualified names of definitions the file gained or lost.
+    fn collect_file_symbols(&mut self, uri: &Url) -> FxHashSet<String> {
+        let Some(path) = self.file_cache.get(uri).map(|file_state| &file_state.path) else {
+            return FxHashSet::default();
+        };
+
+        let mut acc = SymbolAccumulator::default();
+        // keep previous symbols of files that failed to parse rather than losing them all
+        if crate::collect_symbols(&self.c_container, &path.to_string_lossy(), &mut acc) {
+            self.symbol_index.update_file(uri.clone(), acc)
+        } else {
+            FxHashSet::default()
+        }
     }
 
     pub fn load_files_from_disk(&mut self) {
@@ -129,46 +237,75 @@ impl ProjectContainer {
     }
 
     fn load_package_files_from_disk(&mut self, package: &SourcePackage) {
-        let verse_file_paths = utils::collect_files_with_extension(&package.dir_path, "verse");
+        for (path, contents) in self.read_package_files(package) {
+            self.update_source(package, &path, &contents);
+        }
+    }
 
+    /// Reads the .verse files of a package, along with their canonical path.
+    pub fn read_package_files(&self, package: &SourcePackage) -> Vec<(PathBuf, String)> {
+        let verse_file_paths =
+            utils::collect_files_with_extension(&package.dir_path, "verse", &self.settings.files);
+
+        let mut files = Vec::with_capacity(verse_file_paths.len());
         for path in verse_file_paths {
             let Ok(path) = path.canonicalize() else {
                 continue;
             };
 
-            let contents = match fs::read_to_string(&path) {
-                Ok(contents) => contents,
-                Err(err) => {
-                    log::error!("Unable to read snippet file \"{path:?}\": {err}");
-                    continue;
-                }
-            };
-            self.update_source(package, &path, &contents);
+            match fs::read_to_string(&path) {
+                Ok(contents) => files.push((path, contents)),
+                Err(err) => log::error!("Unable to read snippet file \"{path:?}\": {err}"),
+            }
         }
+        files
+    }
+
+    /// Forgets a file deleted from disk.
+    pub fn remove_source(&mut self, uri: &Url) {
+        let Some(file_state) = self.file_cache.remove(uri) else {
+            return;
+        };
+        if let Some(package) = self
+            .packages
+            .iter()
+            .find(|package| file_state.path.starts_with(&package.dir_path))
+        {
+            crate::remove_source(&package.c_package, &file_state.path.to_string_lossy());
+        }
+        // its symbols are dropped on the next build, along with references to them
+        self.dirty_uris.remove(uri);
+        if self.diagnostics.remove(uri).is_some() {
+            self.stale_diagnostic_uris.insert(uri.clone());
+        }
+        self.needs_build = true;
     }
 
     pub fn update_source(&mut self, package: &SourcePackage, path: &Path, contents: &str) {
-        let uri = match Url::from_file_path(&path) {
-            Ok(uri) => uri,
-            Err(_) => {
-                log::error!("Couldn't convert path \"{path:?}\" to Url");
-                return;
-            }
+        let Some(uri) = utils::path_to_uri(path) else {
+            log::error!("Couldn't convert path \"{path:?}\" to Url");
+            return;
         };
 
         let new_line_breaks = contents
             .match_indices('\n')
             .map(|(i, _)| i as u32)
             .collect();
+        self.dirty_uris.insert(uri.clone());
         match self.file_cache.entry(uri) {
             Entry::Occupied(mut entry) => {
-                entry.get_mut().span_source.line_breaks = new_line_breaks;
+                let file_state = entry.get_mut();
+                file_state.span_source.line_breaks = new_line_breaks;
+                file_state.text = contents.to_owned();
+                file_state.path = path.to_owned();
             }
             Entry::Vacant(entry) => {
                 entry.insert(FileState {
                     span_source: SpanSource {
                         line_breaks: new_line_breaks,
                     },
+                    text: contents.to_owned(),
+                    path: path.to_owned(),
                 });
             }
         }
@@ -184,11 +321,6 @@ impl ProjectContainer {
         }
 
         let path_str = path.to_string_lossy();
-        crate::upsert_source(
-            &package.c_package,
-            &path_str,
-            &module_path_to_root,
-            &contents,
-        );
+        crate::upsert_source(&package.c_package, &path_str, module_path_to_root, contents);
     }
 }
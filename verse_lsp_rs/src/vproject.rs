@@ -1,11 +1,95 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use lsp_types::Range;
 use serde::{Deserialize, Serialize};
 
+use crate::utils;
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VProjectFile {
     pub packages: Vec<VProjectPackage>,
 }
 
+impl VProjectFile {
+    pub fn read(path: &Path) -> anyhow::Result<Self> {
+        let contents = fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&contents)?)
+    }
+}
+
+impl VProjectPackage {
+    /// Resolves `dirPath`, which may be relative to the .vproject file
+    /// and contain `~` or environment variables, e.g `$HOME`, `${HOME}` or `%USERPROFILE%`.
+    pub fn resolve_dir_path(&self, vproject_path: &Path) -> anyhow::Result<PathBuf> {
+        let path = PathBuf::from(expand_path_variables(&self.desc.dir_path)?);
+        let path = match vproject_path.parent() {
+            Some(vproject_dir) if path.is_relative() => vproject_dir.join(path),
+            _ => path,
+        };
+        path.canonicalize()
+            .with_context(|| format!("Cannot resolve package directory {path:?}"))
+    }
+}
+
+fn expand_path_variables(path: &str) -> anyhow::Result<String> {
+    expand_path_variables_with(path, |name| env::var(name).ok())
+}
+
+/// Expands `~`, `$VAR`, `${VAR}` and `%VAR%` with variables given by `lookup`.
+fn expand_path_variables_with(
+    path: &str,
+    lookup: impl Fn(&str) -> Option<String>,
+) -> anyhow::Result<String> {
+    let mut expanded = String::with_capacity(path.len());
+    let mut rest = path;
+    if rest == "~" || rest.starts_with("~/") || rest.starts_with("~\\") {
+        let home = lookup("HOME")
+            .or_else(|| lookup("USERPROFILE"))
+            .context("Cannot expand `~`, home directory is unknown")?;
+        expanded.push_str(&home);
+        rest = &rest[1..];
+    }
+
+    while let Some(index) = rest.find(['$', '%']) {
+        expanded.push_str(&rest[..index]);
+        let after = &rest[index + 1..];
+        let variable = if rest[index..].starts_with('%') {
+            after
+                .find('%')
+                .map(|end| (&after[..end], &after[end + 1..]))
+        } else if let Some(braced) = after.strip_prefix('{') {
+            let end = braced.find('}').context("Unclosed `${` in path")?;
+            Some((&braced[..end], &braced[end + 1..]))
+        } else {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            Some((&after[..end], &after[end..]))
+        };
+
+        match variable {
+            Some((name, remaining)) if !name.is_empty() => {
+                let value = lookup(name)
+                    .with_context(|| format!("Environment variable `{name}` is not set"))?;
+                expanded.push_str(&value);
+                rest = remaining;
+            }
+            // lone `$` or `%`
+            _ => {
+                expanded.push_str(&rest[index..index + 1]);
+                rest = after;
+            }
+        }
+    }
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
 pub struct VProjectPackage {
@@ -39,6 +123,22 @@ pub struct PackageSettings {
     pub allow_experimental: bool,
 }
 
+impl Default for PackageSettings {
+    fn default() -> Self {
+        Self {
+            verse_path: String::new(),
+            verse_scope: VerseScope::PublicUser,
+            role: PackageRole::Source,
+            verse_version: None,
+            fortnite_version: None,
+            treat_modules_as_implicit: false,
+            dependency_packages: vec![],
+            vni_dest_dir: None,
+            allow_experimental: false,
+        }
+    }
+}
+
 #[repr(u8)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum VerseScope {
@@ -58,8 +158,359 @@ pub enum PackageRole {
     PersistenceSoftCompatConstraint,
 }
 
+impl VerseScope {
+    pub const ALL: [Self; 4] = [
+        Self::PublicAPI,
+        Self::InternalAPI,
+        Self::PublicUser,
+        Self::InternalUser,
+    ];
+}
+
 impl PackageRole {
+    pub const ALL: [Self; 5] = [
+        Self::Source,
+        Self::External,
+        Self::GenerateCompatConstraint,
+        Self::PersistenceCompatConstraint,
+        Self::PersistenceSoftCompatConstraint,
+    ];
+
     fn source() -> Self {
         Self::Source
     }
 }
+
+/// A string value of a .vproject file, along with where it is located.
+#[derive(Clone, Debug)]
+pub struct JsonStringValue {
+    /// Object keys and array indices leading to the value, e.g `packages[0].desc.name`.
+    pub path: Vec<JsonPathSegment>,
+    pub value: String,
+    /// Range of the string literal, including quotes.
+    pub range: Range,
+    /// Range of the string content, without quotes.
+    pub content_range: Range,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum JsonPathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl JsonStringValue {
+    /// Index of the package the value belongs to, along with the keys leading to the value
+    /// from the package, e.g `["desc", "settings", "dependencyPackages"]`.
+    pub fn package_field(&self) -> Option<(usize, Vec<&str>)> {
+        let [
+            JsonPathSegment::Key(packages),
+            JsonPathSegment::Index(index),
+            field @ ..,
+        ] = self.path.as_slice()
+        else {
+            return None;
+        };
+        if packages != "packages" {
+            return None;
+        }
+        let keys = field
+            .iter()
+            .filter_map(|segment| match segment {
+                JsonPathSegment::Key(key) => Some(key.as_str()),
+                JsonPathSegment::Index(_) => None,
+            })
+            .collect();
+        Some((*index, keys))
+    }
+
+    pub fn is_package_field(&self, package_index: usize, keys: &[&str]) -> bool {
+        self.package_field()
+            .is_some_and(|(index, field)| index == package_index && field == keys)
+    }
+}
+
+/// Collects string values of a JSON document.
+/// Scanning stops at the first syntax error, keeping values found so far so partially
+/// written files can still be inspected.
+pub fn collect_string_values(contents: &str) -> Vec<JsonStringValue> {
+    let mut scanner = JsonScanner {
+        text: contents,
+        offset: 0,
+        path: vec![],
+        values: vec![],
+    };
+    scanner.value();
+    scanner.values
+}
+
+struct JsonScanner<'a> {
+    text: &'a str,
+    offset: usize,
+    path: Vec<JsonPathSegment>,
+    values: Vec<JsonStringValue>,
+}
+
+impl JsonScanner<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.offset).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_whitespace()) {
+            self.offset += 1;
+        }
+    }
+
+    fn value(&mut self) -> Option<()> {
+        self.skip_whitespace();
+        match self.peek()? {
+            b'{' => self.object(),
+            b'[' => self.array(),
+            b'"' => {
+                let start = self.offset;
+                let (value, terminated) = match self.string() {
+                    Ok(value) => (value, true),
+                    Err(partial_value) => (partial_value, false),
+                };
+                let content_end = if terminated {
+                    self.offset - 1
+                } else {
+                    self.offset
+                };
+                self.values.push(JsonStringValue {
+                    path: self.path.clone(),
+                    value,
+                    range: Range::new(
+                        utils::offset_to_position(self.text, start),
+                        utils::offset_to_position(self.text, self.offset),
+                    ),
+                    content_range: Range::new(
+                        utils::offset_to_position(self.text, start + 1),
+                        utils::offset_to_position(self.text, content_end),
+                    ),
+                });
+                terminated.then_some(())
+            }
+            _ => {
+                let start = self.offset;
+                while self.peek().is_some_and(|c| !b",]} \t\r\n".contains(&c)) {
+                    self.offset += 1;
+                }
+                (self.offset > start).then_some(())
+            }
+        }
+    }
+
+    fn object(&mut self) -> Option<()> {
+        self.offset += 1;
+        self.skip_whitespace();
+        if self.peek()? == b'}' {
+            self.offset += 1;
+            return Some(());
+        }
+        loop {
+            self.skip_whitespace();
+            if self.peek()? != b'"' {
+                return None;
+            }
+            let key = self.string().ok()?;
+            self.skip_whitespace();
+            if self.peek()? != b':' {
+                return None;
+            }
+            self.offset += 1;
+
+            self.path.push(JsonPathSegment::Key(key));
+            self.value()?;
+            self.path.pop();
+
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.offset += 1,
+                b'}' => {
+                    self.offset += 1;
+                    return Some(());
+                }
+                _ => return None,
+            }
+        }
+    }
+
+    fn array(&mut self) -> Option<()> {
+        self.offset += 1;
+        self.skip_whitespace();
+        if self.peek()? == b']' {
+            self.offset += 1;
+            return Some(());
+        }
+        for index in 0.. {
+            self.path.push(JsonPathSegment::Index(index));
+            self.value()?;
+            self.path.pop();
+
+            self.skip_whitespace();
+            match self.peek()? {
+                b',' => self.offset += 1,
+                b']' => break,
+                _ => return None,
+            }
+        }
+        self.offset += 1;
+        Some(())
+    }
+
+    /// Reads a string literal. When unterminated, the literal stops at the end of the line
+    /// and its content is still returned as an error.
+    fn string(&mut self) -> Result<String, String> {
+        let start = self.offset;
+        self.offset += 1;
+        loop {
+            match self.peek() {
+                Some(b'"') => {
+                    self.offset += 1;
+                    let literal = &self.text[start..self.offset];
+                    return Ok(serde_json::from_str(literal)
+                        .unwrap_or_else(|_| literal[1..literal.len() - 1].to_owned()));
+                }
+                Some(b'\\') => self.offset += 2,
+                Some(b'\n') | None => {
+                    self.offset = self.offset.min(self.text.len());
+                    return Err(self.text[start + 1..self.offset].to_owned());
+                }
+                Some(_) => self.offset += 1,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use lsp_types::Position;
+
+    use super::*;
+
+    fn key(key: &str) -> JsonPathSegment {
+        JsonPathSegment::Key(key.to_owned())
+    }
+
+    fn expand(path: &str) -> anyhow::Result<String> {
+        expand_path_variables_with(path, |name| match name {
+            "HOME" => Some("/home/verse".to_owned()),
+            "DIGESTS" => Some("/digests".to_owned()),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn expand_home() {
+        assert_eq!(expand("~").unwrap(), "/home/verse");
+        assert_eq!(expand("~/Verse").unwrap(), "/home/verse/Verse");
+        assert_eq!(expand("~\\Verse").unwrap(), "/home/verse\\Verse");
+        // only a leading `~` is the home directory
+        assert_eq!(expand("a/~/b").unwrap(), "a/~/b");
+        assert_eq!(expand("~user/b").unwrap(), "~user/b");
+    }
+
+    #[test]
+    fn expand_home_fallback() {
+        let expanded = expand_path_variables_with("~/Verse", |name| {
+            (name == "USERPROFILE").then(|| "C:\\Users\\verse".to_owned())
+        });
+        assert_eq!(expanded.unwrap(), "C:\\Users\\verse/Verse");
+        assert!(expand_path_variables_with("~", |_| None).is_err());
+    }
+
+    #[test]
+    fn expand_variables() {
+        assert_eq!(expand("$DIGESTS/Verse").unwrap(), "/digests/Verse");
+        assert_eq!(expand("${DIGESTS}Verse").unwrap(), "/digestsVerse");
+        assert_eq!(expand("%DIGESTS%\\Verse").unwrap(), "/digests\\Verse");
+        assert_eq!(expand("$DIGESTS$DIGESTS").unwrap(), "/digests/digests");
+    }
+
+    #[test]
+    fn expand_lone_markers() {
+        assert_eq!(expand("a$").unwrap(), "a$");
+        assert_eq!(expand("a$/b").unwrap(), "a$/b");
+        assert_eq!(expand("100%").unwrap(), "100%");
+        assert_eq!(expand("a%%b").unwrap(), "a%%b");
+    }
+
+    #[test]
+    fn expand_errors() {
+        assert!(expand("$UNSET").is_err());
+        assert!(expand("%UNSET%").is_err());
+        assert!(expand("${DIGESTS").is_err());
+    }
+
+    #[test]
+    fn collect_nested_values() {
+        let values = collect_string_values(r#"{"packages": [{"desc": {"name": "A"}}, "B"]}"#);
+        assert_eq!(values.len(), 2);
+        assert_eq!(
+            values[0].path,
+            [
+                key("packages"),
+                JsonPathSegment::Index(0),
+                key("desc"),
+                key("name")
+            ]
+        );
+        assert_eq!(values[0].value, "A");
+        assert_eq!(
+            values[0].range,
+            Range::new(Position::new(0, 32), Position::new(0, 35))
+        );
+        assert_eq!(
+            values[0].content_range,
+            Range::new(Position::new(0, 33), Position::new(0, 34))
+        );
+        assert_eq!(values[1].path, [key("packages"), JsonPathSegment::Index(1)]);
+        assert_eq!(values[1].value, "B");
+    }
+
+    #[test]
+    fn collect_escaped_values() {
+        let values = collect_string_values(r#"{"a": "x\"y\\zA", "b": "c"}"#);
+        assert_eq!(values.len(), 2);
+        assert_eq!(values[0].value, "x\"y\\zA");
+        assert_eq!(values[1].value, "c");
+    }
+
+    #[test]
+    fn collect_unterminated_value() {
+        let values = collect_string_values("{\"a\": \"bc");
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].value, "bc");
+        assert_eq!(
+            values[0].content_range,
+            Range::new(Position::new(0, 7), Position::new(0, 9))
+        );
+
+        // the literal stops at the end of the line, and scanning with it
+        let values = collect_string_values("{\"a\": \"bc\n, \"d\": \"e\"}");
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].value, "bc");
+
+        // escape at the very end of the file
+        let values = collect_string_values("{\"a\": \"bc\\");
+        assert_eq!(values.len(), 1);
+        assert_eq!(values[0].value, "bc\\");
+    }
+
+    #[test]
+    fn collect_partial_file() {
+        let values = collect_string_values(r#"{"a": "x", "b": ["y", "z""#);
+        let found: Vec<&str> = values.iter().map(|value| value.value.as_str()).collect();
+        assert_eq!(found, ["x", "y", "z"]);
+
+        let values = collect_string_values(r#"{"a": "x", "b": "#);
+        assert_eq!(values.len(), 1);
+
+        let values = collect_string_values(r#"{"a": "x" "b": "y"}"#);
+        assert_eq!(values.len(), 1);
+
+        assert!(collect_string_values("").is_empty());
+    }
+}